@@ -0,0 +1,55 @@
+use criterion::{
+    criterion_group,
+    criterion_main,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+};
+use path_to_unicode_filename::*;
+
+// representative paths: plain POSIX ones, Windows ones under homes, ones full of escaped chars, and non-Latin ones
+fn corpora() -> Vec<(&'static str, Vec<String>)> {
+    let posix = (0..100).map(|n| format!("/var/lib/app/data/{:03}/part-{}.parquet", n % 7, n)).collect();
+    let windows = (0..100).map(|n| format!("C:\\Users\\user{}\\Documents\\Reports\\Q{} report.xlsx", n % 5, n % 4 + 1)).collect();
+    let escaped = (0..100).map(|n| format!("/tmp/a:b*c?\"d\"<e>|f\t{}\\g////h", n)).collect();
+    let non_latin = (0..100).map(|n| format!("/home/пользователь/Документы/日本語のファイル{}.txt", n)).collect();
+    vec![("posix", posix), ("windows", windows), ("escaped", escaped), ("non_latin", non_latin)]
+}
+
+fn bench_codec(c: &mut Criterion) {
+    let codecs = [
+        ("default", Codec::new()),
+        ("compact", Codec::builder().compact_escapes(true).build().unwrap()),
+        ("transliterate", Codec::builder().transliterate(CyrillicToLatin).build().unwrap()),
+    ];
+    for (corpus, paths) in corpora() {
+        let bytes = paths.iter().map(String::len).sum::<usize>() as u64;
+
+        let mut group = c.benchmark_group(format!("encode/{}", corpus));
+        group.throughput(Throughput::Bytes(bytes));
+        for (name, codec) in &codecs {
+            group.bench_with_input(BenchmarkId::from_parameter(name), &paths, |b, paths| {
+                b.iter(|| paths.iter().map(|path| codec.to_filename_from_str(path).len()).sum::<usize>())
+            });
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group(format!("decode/{}", corpus));
+        group.throughput(Throughput::Bytes(bytes));
+        for (name, codec) in &codecs {
+            let filenames: Vec<_> = paths.iter().map(|path| codec.to_filename_from_str(path)).collect();
+            group.bench_with_input(BenchmarkId::from_parameter(name), &filenames, |b, filenames| {
+                b.iter(|| filenames.iter().filter(|filename| codec.to_path_from_str(filename).is_ok()).count())
+            });
+        }
+        group.finish();
+    }
+
+    // the cost of collecting stats on top of encoding
+    let (_, paths) = &corpora()[1];
+    let codec = Codec::builder().collect_stats(true).build().unwrap();
+    c.bench_function("encode/windows/stats", |b| b.iter(|| paths.iter().map(|path| codec.to_filename_from_str(path).len()).sum::<usize>()));
+}
+
+criterion_group!(benches, bench_codec);
+criterion_main!(benches);
@@ -0,0 +1,663 @@
+//! The command line tool of path_to_unicode_filename.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::{
+        self,
+        IsTerminal,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::ExitCode,
+};
+
+use clap::{
+    Args,
+    CommandFactory,
+    Parser,
+    Subcommand,
+    ValueEnum,
+};
+use clap_complete::Shell;
+
+use path_to_unicode_filename::*;
+
+#[derive(Parser)]
+#[command(name = "ptuf", version, about = "Encodes paths to filenames and decodes them back")]
+struct Cli {
+    /// When to colorize the output
+    #[arg(long, value_enum, default_value_t = Color::Auto, global = true)]
+    color: Color,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+// the codec settings, applied in the order of the config file and then the flags overriding it
+#[derive(Args)]
+struct Settings {
+    /// A file holding a config string of `Codec::to_config_string`
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// How spaces are written
+    #[arg(long, global = true, value_parser = ["keep", "open-box", "no-break-space", "ideographic-space"])]
+    spaces: Option<String>,
+    /// How a trailing separator is encoded
+    #[arg(long, global = true, value_parser = ["preserve", "strip", "mark"])]
+    trailing: Option<String>,
+    /// Whether repeated separators are collapsed
+    #[arg(long, global = true)]
+    collapse: Option<bool>,
+    /// Whether ` -> ` separates a link and its target
+    #[arg(long, global = true)]
+    links: Option<bool>,
+    /// Whether runs of escaped chars are written compactly
+    #[arg(long, global = true)]
+    compact: Option<bool>,
+    /// Spells chars outside ASCII in it, like Cyrillic in Latin
+    #[arg(long, global = true, value_parser = ["cyrillic-latin"])]
+    translit: Option<String>,
+    /// Whether filenames start with a header of the settings
+    #[arg(long, global = true)]
+    header: Option<bool>,
+    /// Whether paths are only escaped, without compressing OS and common directories
+    #[arg(long, global = true)]
+    escaping_only: Option<bool>,
+    /// How the drive letter of a windows path is encoded
+    #[arg(long, global = true, value_parser = ["preserve", "uppercase"])]
+    drive_letter_case: Option<String>,
+    /// How a recorded lowercase root is decoded
+    #[arg(long, global = true, value_parser = ["original", "canonical"])]
+    case_restoration: Option<String>,
+    /// What decoding does with unknown icons a filename starts with
+    #[arg(long, global = true, value_parser = ["pass-through", "error", "strip"])]
+    unknown_icons: Option<String>,
+    /// Spells a common directory out instead of compressing it to an icon
+    #[arg(long, global = true, value_name = "DIR", value_parser = ["home", "music", "app-data", "desktop", "documents", "downloads", "pictures", "videos", "caches", "drive"])]
+    uncompressed: Vec<String>,
+    /// Registers a directory as its icon followed by its name, e.g. `🚧Projects`
+    #[arg(long = "dir", global = true, value_name = "ICON+NAME")]
+    dirs: Vec<String>,
+    /// Rejects filenames longer than this many UTF-8 bytes before decoding them
+    #[arg(long, global = true, value_name = "BYTES")]
+    max_input_len: Option<usize>,
+    /// Rejects filenames whose compact escapes expand to more than this many chars before decoding them
+    #[arg(long, global = true, value_name = "CHARS")]
+    max_escape_expansion: Option<usize>,
+    /// Compresses the network shares and bind mounts of a mount table, like `/proc/self/mountinfo`
+    #[arg(long, global = true, value_name = "FILE")]
+    mount_table: Option<PathBuf>,
+    /// Compresses the network shares and bind mounts of this machine
+    #[cfg(target_os = "linux")]
+    #[arg(long, global = true)]
+    local_mounts: bool,
+    /// Compresses the known folders of the current Windows user at their actual locations
+    #[arg(long, global = true)]
+    local_known_folders: bool,
+    /// Expands Windows short names, like `PROGRA~1`, before encoding paths
+    #[arg(long, global = true)]
+    expand_short_names: bool,
+    /// A preset of settings: `ascii` spells Cyrillic in Latin like `--translit cyrillic-latin`, and `bounded`
+    /// makes `encode` print a fixed-size token hashed from each filename after it. There's no anonymized mode,
+    /// since the filenames are meant to decode back to their paths
+    #[arg(long, global = true, value_enum)]
+    mode: Option<Mode>,
+}
+
+impl Settings {
+    fn codec(&self) -> Result<Codec, String> {
+        let mut config = match &self.config {
+            Some(path) => fs::read_to_string(path).map_err(|err| format!("couldn't read {}: {}", path.display(), err))?.trim().to_string(),
+            None => "v1".to_string(),
+        };
+        let flags = [
+            ("spaces", self.spaces.clone()),
+            ("trailing", self.trailing.clone()),
+            ("collapse", self.collapse.map(|collapse| collapse.to_string())),
+            ("links", self.links.map(|links| links.to_string())),
+            ("compact", self.compact.map(|compact| compact.to_string())),
+            ("translit", self.translit.clone().or_else(|| (self.mode == Some(Mode::Ascii)).then(|| "cyrillic-latin".to_string()))),
+            ("header", self.header.map(|header| header.to_string())),
+            ("escaping-only", self.escaping_only.map(|escaping_only| escaping_only.to_string())),
+            ("drive", self.drive_letter_case.clone()),
+            ("case", self.case_restoration.clone()),
+            ("unknown", self.unknown_icons.clone()),
+        ];
+        for (key, value) in flags {
+            if let Some(value) = value {
+                config.push_str(&format!(";{}={}", key, value));
+            }
+        }
+        if !self.uncompressed.is_empty() {
+            config.push_str(&format!(";uncompressed={}", self.uncompressed.join("/")));
+        }
+        for dir in &self.dirs {
+            // names are percent-escaped in config strings, so they can hold its separators
+            let mut escaped = String::new();
+            for c in dir.chars() {
+                match c {
+                    '%' | ';' | '=' => escaped.push_str(&format!("%{:02X}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            config.push_str(&format!(";dirs={}", escaped));
+        }
+
+        // the settings config strings don't hold
+        let mut builder = CodecBuilder::from_config_string(&config).map_err(|err| format!("{:?}", err))?;
+        if let Some(len) = self.max_input_len {
+            builder = builder.max_input_len(len);
+        }
+        if let Some(chars) = self.max_escape_expansion {
+            builder = builder.max_escape_expansion(chars);
+        }
+        if let Some(path) = &self.mount_table {
+            builder = builder.mount_table(fs::read_to_string(path).map_err(|err| format!("couldn't read {}: {}", path.display(), err))?);
+        }
+        #[cfg(target_os = "linux")]
+        if self.local_mounts {
+            builder = builder.local_mounts().map_err(|err| format!("{:?}", err))?;
+        }
+        if self.local_known_folders {
+            builder = builder.local_known_folders().map_err(|err| format!("{:?}", err))?;
+        }
+        builder.expand_short_names(self.expand_short_names).build().map_err(|err| format!("{:?}", err))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Ascii,
+    Bounded,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the filenames of paths, one per line
+    Encode {
+        paths: Vec<PathBuf>,
+        /// The hash of the tokens of `--mode bounded`
+        #[arg(long, value_enum, default_value_t = Hasher::Xxh3)]
+        hasher: Hasher,
+    },
+    /// Prints the breakdown of an encoded filename, segment by segment
+    Explain {
+        name: String,
+    },
+    /// Reports the names in a directory of encoded artifacts that need attention
+    Check {
+        dir: PathBuf,
+        /// The longest allowed name, in the unit of `--fs`
+        #[arg(long, default_value_t = 255)]
+        max_len: usize,
+        /// The filesystem whose length unit `--max-len` is in
+        #[arg(long, value_enum, default_value_t = Fs::Utf8)]
+        fs: Fs,
+        /// Reports the names of other platforms than this, which doesn't change how the names are decoded
+        #[arg(long, value_enum)]
+        expect_platform: Option<Platform>,
+        /// Prints the issues as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Lists the encoded artifacts in a directory next to their decoded original paths
+    Ls {
+        dir: PathBuf,
+        /// Only lists the artifacts whose original paths match this glob, like `/home/*/src/**/*.rs`
+        #[arg(long = "where", value_name = "GLOB")]
+        glob: Option<String>,
+    },
+    /// Prints the completion script of a shell
+    Completions {
+        shell: Shell,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Hasher {
+    Blake3,
+    Xxh3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Platform {
+    Mac,
+    Linux,
+    Windows,
+}
+
+impl From<Platform> for PlatformKind {
+    fn from(platform: Platform) -> Self {
+        match platform {
+            Platform::Mac => PlatformKind::Mac,
+            Platform::Linux => PlatformKind::Linux,
+            Platform::Windows => PlatformKind::Windows,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Fs {
+    Utf8,
+    Ntfs,
+    HfsPlus,
+}
+
+impl From<Fs> for FsProfile {
+    fn from(fs: Fs) -> Self {
+        match fs {
+            Fs::Utf8 => FsProfile::Utf8,
+            Fs::Ntfs => FsProfile::Ntfs,
+            Fs::HfsPlus => FsProfile::HfsPlus,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Color {
+    Auto,
+    Always,
+    Never,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let color = match cli.color {
+        Color::Auto => io::stdout().is_terminal(),
+        Color::Always => true,
+        Color::Never => false,
+    };
+
+    let codec = match cli.settings.codec() {
+        Ok(codec) => codec,
+        Err(err) => {
+            eprintln!("{} {}", paint("error:", RED, color), err);
+            return ExitCode::FAILURE;
+        },
+    };
+    match cli.command {
+        Command::Encode { paths, hasher } => {
+            let hasher: Option<Box<dyn NameHasher>> = match (cli.settings.mode, hasher) {
+                (Some(Mode::Bounded), Hasher::Blake3) => Some(Box::new(Blake3Hasher::new())),
+                (Some(Mode::Bounded), Hasher::Xxh3) => Some(Box::new(Xxh3Hasher::new())),
+                _ => None,
+            };
+            match encode(&codec, &paths, hasher.as_deref()) {
+                Ok(filenames) => {
+                    print!("{}", filenames);
+                    ExitCode::SUCCESS
+                },
+                Err(err) => {
+                    eprintln!("{} {:?}", paint("error:", RED, color), err);
+                    ExitCode::FAILURE
+                },
+            }
+        },
+        Command::Explain { name } => {
+            let (report, ok) = explain(&codec, &name, color);
+            print!("{}", report);
+            if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        },
+        Command::Check { dir, max_len, fs, expect_platform, json } => {
+            let names = match read_names(&dir) {
+                Ok(names) => names,
+                Err(err) => {
+                    eprintln!("{} couldn't read {}: {}", paint("error:", RED, color), dir.display(), err);
+                    return ExitCode::FAILURE;
+                },
+            };
+            let issues = check(&codec, &names, max_len, fs.into(), expect_platform.map(PlatformKind::from));
+            if json {
+                println!("{}", issues_to_json(&issues));
+            } else {
+                for issue in &issues {
+                    println!("{} {:?}: {}", paint(&format!("{}:", issue.kind), YELLOW, color), issue.name, issue.message);
+                }
+            }
+            if issues.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        },
+        Command::Ls { dir, glob } => {
+            let glob = match glob.map(Glob::new).transpose() {
+                Ok(glob) => glob,
+                Err(err) => {
+                    eprintln!("{} {:?}", paint("error:", RED, color), err);
+                    return ExitCode::FAILURE;
+                },
+            };
+            let names = match read_names(&dir) {
+                Ok(names) => names,
+                Err(err) => {
+                    eprintln!("{} couldn't read {}: {}", paint("error:", RED, color), dir.display(), err);
+                    return ExitCode::FAILURE;
+                },
+            };
+            print!("{}", ls(&codec, &names, glob.as_ref()));
+            ExitCode::SUCCESS
+        },
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "ptuf", &mut io::stdout());
+            ExitCode::SUCCESS
+        },
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+
+fn paint(s: &str, style: &str, color: bool) -> String {
+    if color && !style.is_empty() {
+        format!("{}{}{}", style, s, RESET)
+    } else {
+        s.to_string()
+    }
+}
+
+// the filenames of `paths`, each followed by a tab and its token if there's a hasher
+fn encode(codec: &Codec, paths: &[PathBuf], hasher: Option<&dyn NameHasher>) -> Result<String, Error> {
+    let mut filenames = String::new();
+    for path in paths {
+        match hasher {
+            Some(hasher) => {
+                let (filename, token) = codec.to_filename_and_token(path, hasher)?;
+                filenames.push_str(&format!("{}\t{}\n", filename, token));
+            },
+            None => filenames.push_str(&format!("{}\n", codec.to_filename(path)?)),
+        }
+    }
+    Ok(filenames)
+}
+
+// returns the report and whether the name decodes
+fn explain(codec: &Codec, name: &str, color: bool) -> (String, bool) {
+    let parsed = match codec.parse(name) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            let mut report = format!("{} {:?} doesn't decode: {:?}\n", paint("error:", RED, color), name, err);
+            for fix in codec.suggest_fix(name) {
+                let replacement = match &fix.replacement {
+                    Some(replacement) => format!(", replace {:?} with {:?}", &name[fix.span.clone()], replacement),
+                    None => String::new(),
+                };
+                report.push_str(&format!("{} {}{}\n", paint("hint:", YELLOW, color), fix.message, replacement));
+            }
+            return (report, false);
+        },
+    };
+
+    let mut report = String::new();
+    for segment in &parsed.segments {
+        let text = &name[segment.span.clone()];
+        let (label, detail, style) = match &segment.kind {
+            SegmentKind::PlatformIcon(kind) => ("platform", format!("{:?}", kind), BLUE),
+            SegmentKind::LowercaseRoot => ("lowercase root", String::new(), BLUE),
+            SegmentKind::CommonDirIcon(dir) => ("common dir", format!("{:?}", dir), CYAN),
+            SegmentKind::RegisteredDirIcon(dir) => ("registered dir", dir.clone(), CYAN),
+            SegmentKind::UserToken(user) => ("username", user.clone(), GREEN),
+            SegmentKind::EscapedChar(c) => ("escaped char", format!("{:?}", c), MAGENTA),
+            SegmentKind::CompactEscape(c, n) => ("compact escape", format!("{:?} x {}", c, n), MAGENTA),
+            SegmentKind::Transliteration(original) => ("transliteration", format!("{:?}", original), MAGENTA),
+            SegmentKind::Literal => ("literal", String::new(), ""),
+            SegmentKind::TrailingSeparator => ("trailing separator", String::new(), YELLOW),
+            SegmentKind::Counter(n) => ("counter", n.to_string(), YELLOW),
+            SegmentKind::LinkArrow => ("link arrow", String::new(), YELLOW),
+            SegmentKind::Header(config) => ("header", config.clone(), BOLD),
+        };
+        let label = format!("{:<18}", label);
+        let line = format!("{:>3}..{:<3} {} {} {}", segment.span.start, segment.span.end, paint(&label, style, color), text, detail);
+        report.push_str(line.trim_end());
+        report.push('\n');
+    }
+    if let Ok(path) = codec.to_path_from_str(name) {
+        report.push_str(&format!("{} {}\n", paint("path:", BOLD, color), path.display()));
+    }
+    (report, true)
+}
+
+// the sorted names of the entries of `dir`, as they are on disk
+fn read_names(dir: &Path) -> io::Result<Vec<OsString>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        names.push(entry?.file_name());
+    }
+    names.sort();
+    Ok(names)
+}
+
+// the names that decode to paths `glob` matches, padded into a column before the paths, where names that
+// don't decode, like the ones that aren't UTF-8, are left to `ptuf check`
+fn ls(codec: &Codec, names: &[OsString], glob: Option<&Glob>) -> String {
+    let rows: Vec<_> = names
+        .iter()
+        .filter_map(|name| name.to_str())
+        .filter_map(|name| Some((name, codec.to_path_from_str(name).ok()?)))
+        .filter(|(_, path)| glob.is_none_or(|glob| glob.matches(path)))
+        .collect();
+    let width = rows.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    let mut listing = String::new();
+    for (name, path) in rows {
+        listing.push_str(&format!("{:<width$}  {}\n", name, path.display(), width = width));
+    }
+    listing
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Issue {
+    name: String,
+    kind: &'static str,
+    message: String,
+}
+
+// finds the names that don't decode, collide after case-folding, are too long, are of another platform than
+// the given one, or that this codec wouldn't produce, like the ones of another version or configuration
+fn check(codec: &Codec, names: &[OsString], max_len: usize, profile: FsProfile, platform: Option<PlatformKind>) -> Vec<Issue> {
+    let mut utf8_names = Vec::new();
+    let mut issues = Vec::new();
+    for name in names {
+        match name.to_str() {
+            Some(name) => utf8_names.push(name.to_string()),
+            // only written lossily, with the bytes in the message
+            None => issues.push(Issue { name: name.to_string_lossy().into_owned(), kind: "undecodable", message: format!("{:?}", Error::CouldntEncodeToUtf8(name.clone())) }),
+        }
+    }
+    let names = utf8_names;
+    let mut folded: HashMap<String, Vec<&String>> = HashMap::new();
+    for name in &names {
+        folded.entry(name.to_lowercase()).or_default().push(name);
+    }
+
+    for name in &names {
+        let issue = |kind, message| Issue { name: name.clone(), kind, message };
+        match codec.to_path_from_str(name) {
+            Ok(path) => {
+                let encoded = codec.to_filename(&path);
+                if encoded.as_ref() != Ok(name) {
+                    issues.push(issue("noncanonical", format!("{} is encoded to {:?} here", path.display(), encoded.unwrap_or_default())));
+                }
+            },
+            Err(err) => issues.push(issue("undecodable", format!("{:?}", err))),
+        }
+        let others: Vec<_> = folded[&name.to_lowercase()].iter().filter(|other| **other != name).map(|other| format!("{:?}", other)).collect();
+        if !others.is_empty() {
+            issues.push(issue("case-collision", format!("collides with {} after case-folding", others.join(", "))));
+        }
+        if let Some(platform) = platform {
+            let detected = codec.detect_platform_of_filename(name);
+            if detected != Some(platform) {
+                let detected = detected.map_or("no platform".to_string(), |detected| format!("{:?}", detected));
+                issues.push(issue("platform", format!("is of {} instead of {:?}", detected, platform)));
+            }
+        }
+        let len = profile.filename_len(name);
+        if len > max_len {
+            issues.push(issue("too-long", format!("{} is over the budget of {}", len, max_len)));
+        }
+    }
+    issues
+}
+
+fn issues_to_json(issues: &[Issue]) -> String {
+    let issues: Vec<_> = issues.iter().map(|issue| {
+        format!("{{\"name\":{},\"kind\":{},\"message\":{}}}", json_string(&issue.name), json_string(issue.kind), json_string(&issue.message))
+    }).collect();
+    format!("[{}]", issues.join(","))
+}
+
+fn json_string(s: &str) -> String {
+    let mut r = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => r.push_str("\\\""),
+            '\\' => r.push_str("\\\\"),
+            c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+            c => r.push(c),
+        }
+    }
+    r.push('"');
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_name() {
+        let (report, ok) = explain(&Codec::new(), "🍎📄alice／my＂file＂.txt📂", false);
+        assert!(ok);
+        assert_eq!(report, [
+            "  0..4   platform           🍎 Mac",
+            "  4..8   common dir         📄 Documents",
+            "  8..13  username           alice alice",
+            " 13..16  escaped char       ／ '/'",
+            " 16..18  literal            my",
+            " 18..21  escaped char       ＂ '\"'",
+            " 21..25  literal            file",
+            " 25..28  escaped char       ＂ '\"'",
+            " 28..32  literal            .txt",
+            " 32..36  trailing separator 📂",
+            "path: /Users/alice/Documents/my\"file\".txt/",
+            "",
+        ].join("\n"));
+
+        let (report, ok) = explain(&Codec::new(), "🍎invalid", false);
+        assert!(!ok);
+        assert_eq!(report, [
+            "error: \"🍎invalid\" doesn't decode: ParseError(Error { input: \"invalid\", code: Char })",
+            "hint: unknown icon i after 🍎, replace \"🍎\" with \"🍏\"",
+            "",
+        ].join("\n"));
+
+        let (report, _) = explain(&Codec::new(), "／a", true);
+        assert_eq!(report, "  0..3   \u{1b}[35mescaped char      \u{1b}[0m ／ '/'\n  3..4   literal            a\n\u{1b}[1mpath:\u{1b}[0m /a\n");
+    }
+
+    #[test]
+    fn check_names() {
+        let names: Vec<OsString> = ["／tmp／a", "／TMP／A", "🍎invalid", "／tmp／b〔41〕", "x".repeat(20).as_str()].iter().map(OsString::from).collect();
+        let issue = |name: &str, kind, message: &str| Issue { name: name.into(), kind, message: message.into() };
+        assert_eq!(check(&Codec::new(), &names, 16, FsProfile::Utf8, None), vec![
+            issue("／tmp／a", "case-collision", "collides with \"／TMP／A\" after case-folding"),
+            issue("／TMP／A", "case-collision", "collides with \"／tmp／a\" after case-folding"),
+            issue("🍎invalid", "undecodable", "ParseError(Error { input: \"invalid\", code: Char })"),
+            issue("／tmp／b〔41〕", "noncanonical", "/tmp/bA is encoded to \"／tmp／bA\" here"),
+            issue("／tmp／b〔41〕", "too-long", "18 is over the budget of 16"),
+            issue(&"x".repeat(20), "too-long", "20 is over the budget of 16"),
+        ]);
+        assert_eq!(check(&Codec::new(), &names[..1], 255, FsProfile::Utf8, None), vec![]);
+
+        let json = issues_to_json(&[issue("a\"\\\n", "too-long", "x")]);
+        assert_eq!(json, r#"[{"name":"a\"\\\u000a","kind":"too-long","message":"x"}]"#);
+        assert_eq!(issues_to_json(&[]), "[]");
+
+        assert_eq!(check(&Codec::new(), &names[..1], 255, FsProfile::Utf8, Some(PlatformKind::Mac)), vec![
+            issue("／tmp／a", "platform", "is of no platform instead of Mac"),
+        ]);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStringExt;
+
+            // not read as the lossy `／tmp／a\u{FFFD}`, which would decode
+            let names = [OsString::from_vec(b"\xEF\xBC\x8Ftmp\xEF\xBC\x8Fa\xFF".to_vec())];
+            assert_eq!(check(&Codec::new(), &names, 255, FsProfile::Utf8, None), vec![
+                issue("／tmp／a\u{FFFD}", "undecodable", "CouldntEncodeToUtf8(\"／tmp／a\\xFF\")"),
+            ]);
+            assert_eq!(ls(&Codec::new(), &names, None), "");
+        }
+    }
+
+    #[test]
+    fn ls_names() {
+        let names: Vec<OsString> = ["／tmp／a.txt", "／tmp／b.png", "／var／log／long.txt", "🍎invalid"].iter().map(OsString::from).collect();
+        assert_eq!(ls(&Codec::new(), &names, None), [
+            "／tmp／a.txt         /tmp/a.txt",
+            "／tmp／b.png         /tmp/b.png",
+            "／var／log／long.txt  /var/log/long.txt",
+            "",
+        ].join("\n"));
+        assert_eq!(ls(&Codec::new(), &names, Some(&Glob::new("/**/*.txt").unwrap())), [
+            "／tmp／a.txt         /tmp/a.txt",
+            "／var／log／long.txt  /var/log/long.txt",
+            "",
+        ].join("\n"));
+        assert_eq!(ls(&Codec::new(), &names, Some(&Glob::new("/usr/*").unwrap())), "");
+    }
+
+    #[test]
+    fn settings() {
+        Cli::command().debug_assert();
+
+        let codec = |args: &[&str]| Cli::try_parse_from([&["ptuf"], args, &["explain", "a"]].concat()).unwrap().settings.codec();
+        assert_eq!(codec(&[]).unwrap().to_config_string(), Codec::new().to_config_string());
+        assert_eq!(
+            codec(&["--spaces", "open-box", "--compact", "true", "--translit", "cyrillic-latin", "--header", "true", "--escaping-only", "true", "--unknown-icons", "error", "--uncompressed", "drive", "--dir", "🚧Projects", "--dir", "🌳a;b=c%"]).unwrap().to_config_string(),
+            "v1;spaces=open-box;trailing=preserve;collapse=false;links=false;compact=true;translit=cyrillic-latin;header=true;escaping-only=true;drive=preserve;case=original;unknown=error;uncompressed=drive;local=;dirs=🚧Projects/🌳a%3Bb%3Dc%25",
+        );
+        assert!(codec(&["--dir", "🚧"]).is_err());
+        assert!(Cli::try_parse_from(["ptuf", "--spaces", "tab", "explain", "a"]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        fs::write(&path, "v1;trailing=mark;links=true\n").unwrap();
+        let config = path.to_str().unwrap();
+        assert_eq!(
+            codec(&["--config", config, "--links", "false"]).unwrap().to_config_string(),
+            "v1;spaces=keep;trailing=mark;collapse=false;links=false;compact=false;translit=;header=false;escaping-only=false;drive=preserve;case=original;unknown=pass-through;uncompressed=;local=;dirs=",
+        );
+        fs::remove_file(&path).unwrap();
+        assert!(codec(&["--config", config]).is_err());
+
+        assert_eq!(codec(&["--mode", "ascii"]).unwrap().to_config_string(), codec(&["--translit", "cyrillic-latin"]).unwrap().to_config_string());
+        assert!(codec(&["--max-input-len", "4"]).unwrap().to_path_from_str("／abcd").is_err());
+        assert!(codec(&["--max-escape-expansion", "4"]).unwrap().to_path_from_str("〔41*5〕").is_err());
+        fs::write(&path, "nas:/export /mnt/music nfs rw 0 0\n").unwrap();
+        let mounted = codec(&["--mount-table", config]).unwrap();
+        assert_eq!(mounted.to_filename("/mnt/music/x"), Ok("🐧🥞〇nas：%2Fexport／x".into()));
+        assert!(mounted.to_config_string().contains(";local=mounts;"));
+        assert!(codec(&["--mount-table", config, "--header", "true"]).is_err());
+    }
+
+    #[test]
+    fn encode_paths() {
+        let paths = [PathBuf::from("/tmp/a"), PathBuf::from("/tmp/b")];
+        assert_eq!(encode(&Codec::new(), &paths, None), Ok("／tmp／a\n／tmp／b\n".into()));
+        let bounded = encode(&Codec::new(), &paths[..1], Some(&Xxh3Hasher::new())).unwrap();
+        let (filename, token) = bounded.trim_end().split_once('\t').unwrap();
+        assert_eq!(filename, "／tmp／a");
+        assert_eq!(Ok((filename.to_string(), token.to_string())), Codec::new().to_filename_and_token("/tmp/a", Xxh3Hasher::new()));
+        assert_eq!(token.len(), 16);
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+/// A bounded LRU cache of decoded filenames, keyed by the encoded string.
+///
+/// Set it up with [`CodecBuilder::decode_cache`](crate::CodecBuilder::decode_cache); the clones of the built [`Codec`](crate::Codec) share it.
+#[derive(Debug, Clone)]
+pub struct DecodeCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (u64, PathBuf, Option<u32>)>,
+    // the ticks of the last uses, oldest first
+    order: BTreeMap<u64, String>,
+}
+
+impl DecodeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, tick: 0, entries: HashMap::new(), order: BTreeMap::new() }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Returns the decoded path and counter of `filename`, marking it as the most recently used.
+    pub fn get(&mut self, filename: &str) -> Option<(PathBuf, Option<u32>)> {
+        self.tick += 1;
+        let (tick, path, counter) = self.entries.get_mut(filename)?;
+        self.order.remove(tick);
+        *tick = self.tick;
+        self.order.insert(self.tick, filename.to_string());
+        Some((path.clone(), *counter))
+    }
+
+    /// Stores the decoded path and counter of `filename`, evicting the least recently used entry if it's full.
+    pub fn insert(&mut self, filename: &str, path: PathBuf, counter: Option<u32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((tick, _, _)) = self.entries.insert(filename.to_string(), (self.tick, path, counter)) {
+            self.order.remove(&tick);
+        } else if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.insert(self.tick, filename.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lru() {
+        let mut cache = DecodeCache::new(2);
+        cache.insert("a", "/a".into(), None);
+        cache.insert("b", "/b".into(), Some(2));
+        assert_eq!(cache.get("a"), Some(("/a".into(), None)));
+        cache.insert("c", "/c".into(), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(("/a".into(), None)));
+        assert_eq!(cache.get("c"), Some(("/c".into(), None)));
+
+        cache.insert("c", "/cc".into(), Some(1));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("c"), Some(("/cc".into(), Some(1))));
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let mut cache = DecodeCache::new(0);
+        cache.insert("a", "/a".into(), None);
+        assert_eq!(cache.get("a"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn cached_codec() {
+        let codec = crate::Codec::builder().decode_cache(1).build().unwrap();
+        let clone = codec.clone();
+        assert_eq!(codec.to_path("／tmp🔢3"), Ok("/tmp".into()));
+        assert_eq!(clone.to_path_and_counter("／tmp🔢3"), Ok(("/tmp".into(), Some(3))));
+        assert_eq!(clone.decode_cache.as_ref().unwrap().lock().unwrap().len(), 1);
+        assert!(codec.to_path("🍎invalid").is_err());
+        assert_eq!(codec.to_path("🍎🏠alice"), Ok("/Users/alice".into()));
+        assert_eq!(codec.to_path("／tmp"), Ok("/tmp".into()));
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    ffi::OsStr,
+    path::PathBuf,
+};
+
+use super::*;
+
+// bumped whenever the syntax or the encoding of a config string changes, like for a new setting whose default
+// doesn't encode as before, so old strings keep meaning what they meant
+const CONFIG_VERSION: &str = "v1";
+
+impl Codec {
+    /// Describes the settings affecting the encoding in a compact, versioned string, like
+    /// `v1;spaces=open-box;trailing=mark;collapse=false;links=false;compact=false;translit=;header=false;escaping-only=false;drive=preserve;case=original;unknown=pass-through;uncompressed=drive;local=;dirs=🚧Projects`.
+    ///
+    /// The `v1` format is frozen as of 0.2: later releases read every `v1` string and encode and decode with it
+    /// like 0.2 does, and changing either bumps the version, which the tests check on the filenames of
+    /// `tests/golden/v1.tsv`. So the string can be stored next to encoded data and passed to
+    /// [`Codec::from_config_string`] later to decode it identically. The decode cache isn't included,
+    /// since it doesn't change the results.
+    ///
+    /// The settings depending on the machine, the relocations of [`CodecBuilder::mount_table`],
+    /// [`CodecBuilder::local_mounts`] and [`CodecBuilder::local_known_folders`] and, on Windows,
+    /// [`CodecBuilder::expand_short_names`], aren't held by the string. It only names them, like `local=mounts`,
+    /// and [`Codec::from_config_string`] rejects it, so the codec isn't rebuilt without them. Registered dir icons are emoji and symbols, so they never clash with
+    /// the delimiters of the string.
+    pub fn to_config_string(&self) -> String {
+        #[cfg(feature = "platform")]
+        let (drive, case, unknown) = (
+            drive_letter_case_name(self.drive_letter_case),
+            case_restoration_name(self.case_restoration),
+            unknown_icon_policy_name(self.unknown_icons),
+        );
+        #[cfg(not(feature = "platform"))]
+        let (drive, case, unknown) = ("preserve", "original", "pass-through");
+        #[cfg(feature = "platform")]
+        let uncompressed: Vec<_> = COMMON_DIRS.iter().filter(|(dir, _)| self.uncompressed.contains(dir)).map(|(_, name)| *name).collect();
+        #[cfg(not(feature = "platform"))]
+        let uncompressed: Vec<&str> = Vec::new();
+
+        let dirs: Vec<_> = self.dirs.iter().map(|dir| format!("{}{}", dir.icon, escape_config_value(&dir.name))).collect();
+        format!(
+            "{};spaces={};trailing={};collapse={};links={};compact={};translit={};header={};escaping-only={};drive={};case={};unknown={};uncompressed={};local={};dirs={}",
+            CONFIG_VERSION,
+            spaces_name(self.spaces),
+            trailing_separator_name(self.trailing_separator),
+            self.collapse_separators,
+            self.link_notation,
+            self.escaper.compact_escapes,
+            self.escaper.transliterator.as_ref().map(|transliterator| escape_config_value(transliterator.name())).unwrap_or_default(),
+            self.header.is_some(),
+            self.escaping_only,
+            drive,
+            case,
+            unknown,
+            uncompressed.join("/"),
+            self.local_settings().join("/"),
+            dirs.join("/"),
+        )
+    }
+
+    /// Builds a codec from a string made by [`Codec::to_config_string`].
+    ///
+    /// Settings missing from the string are the defaults, and unknown versions, keys and values are
+    /// [`Error::InvalidConfig`], as well as registered dirs [`CodecBuilder::build`] rejects, transliterators
+    /// other than the built-in ones, and settings depending on the machine named in `local=`. Without the
+    /// `platform` feature, strings without `escaping-only=true` are rejected too, since the filenames of their
+    /// codecs don't decode the same in such builds.
+    pub fn from_config_string(config: impl AsRef<str>) -> Result<Codec, Error> {
+        let (builder, escaping_only) = parse_config_string(config.as_ref())?;
+        let codec = builder.escaping_only(escaping_only).build()?;
+        check_escaping_only(escaping_only)?;
+        Ok(codec)
+    }
+
+    /// Decodes a filename with the settings in its header of [`CodecBuilder::header`], or with the settings of
+    /// this codec if it has none.
+    ///
+    /// So a directory of filenames made by differently configured codecs with headers decodes without knowing
+    /// their configurations. Headers whose settings [`Codec::from_config_string`] rejects, like ones of a later
+    /// version, are [`Error::InvalidConfig`]. Filenames starting with a `🧾` that doesn't close a header are
+    /// decoded with this codec, but the ones of codecs without headers starting with what reads as a header, like
+    /// `🧾v1🧾x` for the path `🧾v1🧾x`, are decoded with the header.
+    pub fn decode_auto(&self, filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+        let filename = filename.as_ref();
+        let Some(filename) = filename.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(filename.into()));
+        };
+        match split_header(filename) {
+            Ok(Some((config, rest))) => {
+                self.check_limits(filename)?;
+                // the header leaves out its own setting, which escapes the header icons of the rest
+                Codec::from_config_string(format!("{};header=true", config))?.to_path_from_str(rest)
+            },
+            Ok(None) | Err(_) => self.to_path_from_str(filename),
+        }
+    }
+
+    // the names of the settings depending on the machine, which config strings can't hold
+    pub(crate) fn local_settings(&self) -> Vec<&'static str> {
+        #[cfg(feature = "platform")]
+        let relocated = |mount: bool| self.relocations.iter().any(|r| r.mount == mount);
+        #[cfg(not(feature = "platform"))]
+        let relocated = |_: bool| false;
+        #[cfg(all(windows, feature = "short-names"))]
+        let expand_short_names = self.expand_short_names;
+        #[cfg(not(all(windows, feature = "short-names")))]
+        let expand_short_names = false;
+        [("known-folders", relocated(false)), ("mounts", relocated(true)), ("short-names", expand_short_names)]
+            .into_iter()
+            .filter_map(|(name, set)| set.then_some(name))
+            .collect()
+    }
+
+    // the config string without the settings of the defaults, like `v1;compact=true`, which stays the same when
+    // a setting is added with a default encoding as before
+    pub(crate) fn minimal_config_string(&self) -> String {
+        let config = self.to_config_string();
+        let default = Codec::new().to_config_string();
+        // `escaping-only=false` is the default of the string in every build, though the builds without the
+        // platform feature always escape only
+        let defaults: Vec<_> = default.split(';').skip(1).filter(|field| !field.starts_with("escaping-only=")).chain(["escaping-only=false"]).collect();
+        let fields: Vec<_> = config.split(';').filter(|field| !defaults.contains(field)).collect();
+        fields.join(";")
+    }
+
+    // the header of the codec, the minimal config string without the header setting, escaped like a path
+    pub(crate) fn header_string(&self) -> String {
+        let config = self.minimal_config_string();
+        let fields: Vec<_> = config.split(';').filter(|field| !field.starts_with("header=")).collect();
+        format!("{}{}{}", HEADER_ICON, header_escaper().escape(&fields.join(";")), HEADER_ICON)
+    }
+
+    // the filename after the header of the codec, where the header of another codec is an error, and a codec
+    // without headers reads none
+    pub(crate) fn strip_header<'a>(&self, filename: &'a str) -> Result<&'a str, Error> {
+        let Some(header) = self.header.as_deref() else {
+            return Ok(filename);
+        };
+        if !filename.starts_with(HEADER_ICON) {
+            return Ok(filename);
+        }
+        filename.strip_prefix(header).ok_or_else(|| Error::ParseError(nom::error::Error { input: filename.to_string(), code: nom::error::ErrorKind::Tag }))
+    }
+}
+
+impl CodecBuilder {
+    /// Starts a builder with the settings of a string made by [`Codec::to_config_string`], so the ones it doesn't
+    /// hold, like [`CodecBuilder::max_input_len`], can be added before building.
+    ///
+    /// It fails like [`Codec::from_config_string`], except for the registered dirs left to
+    /// [`CodecBuilder::build`].
+    pub fn from_config_string(config: impl AsRef<str>) -> Result<CodecBuilder, Error> {
+        let (builder, escaping_only) = parse_config_string(config.as_ref())?;
+        check_escaping_only(escaping_only)?;
+        Ok(builder.escaping_only(escaping_only))
+    }
+}
+
+// the builder of the settings of a config string, and whether it escapes only
+fn parse_config_string(config: &str) -> Result<(CodecBuilder, bool), Error> {
+    let invalid = |message: String| Error::InvalidConfig(message);
+
+    let mut fields = config.split(';');
+    match fields.next() {
+        Some(CONFIG_VERSION) => (),
+        version => return Err(invalid(format!("unsupported config version {:?}", version.unwrap_or_default()))),
+    }
+
+    let mut builder = Codec::builder();
+    // a missing key means the compression of the default codec of the platform feature
+    let mut escaping_only = false;
+    for field in fields {
+        let Some((key, value)) = field.split_once('=') else {
+            return Err(invalid(format!("config field {:?} isn't a key=value pair", field)));
+        };
+        let unknown = || invalid(format!("unknown value {:?} of the config key {:?}", value, key));
+        builder = match key {
+            "spaces" => builder.spaces(parse_name(value, SPACES).ok_or_else(unknown)?),
+            "trailing" => builder.trailing_separator(parse_name(value, TRAILING_SEPARATORS).ok_or_else(unknown)?),
+            "collapse" => builder.collapse_separators(value.parse().map_err(|_| unknown())?),
+            "links" => builder.link_notation(value.parse().map_err(|_| unknown())?),
+            "compact" => builder.compact_escapes(value.parse().map_err(|_| unknown())?),
+            "translit" => match unescape_config_value(value).ok_or_else(unknown)?.as_str() {
+                "" => builder,
+                name if name == CyrillicToLatin.name() => builder.transliterate(CyrillicToLatin),
+                _ => return Err(invalid(format!("unknown transliterator {:?}", value))),
+            },
+            "header" => builder.header(value.parse().map_err(|_| unknown())?),
+            "escaping-only" => {
+                escaping_only = value.parse().map_err(|_| unknown())?;
+                builder
+            },
+            #[cfg(feature = "platform")]
+            "drive" => builder.drive_letter_case(parse_name(value, DRIVE_LETTER_CASES).ok_or_else(unknown)?),
+            #[cfg(feature = "platform")]
+            "case" => builder.case_restoration(parse_name(value, CASE_RESTORATIONS).ok_or_else(unknown)?),
+            #[cfg(feature = "platform")]
+            "unknown" => builder.unknown_icons(parse_name(value, UNKNOWN_ICON_POLICIES).ok_or_else(unknown)?),
+            #[cfg(feature = "platform")]
+            "uncompressed" => {
+                for dir in value.split('/').filter(|dir| !dir.is_empty()) {
+                    builder = builder.compress_dir(parse_name(dir, COMMON_DIRS).ok_or_else(unknown)?, false);
+                }
+                builder
+            },
+            #[cfg(not(feature = "platform"))]
+            "drive" | "case" | "unknown" | "uncompressed" => match value {
+                "preserve" | "original" | "pass-through" | "" => builder,
+                _ => return Err(invalid(format!("the config key {:?} needs the platform feature", key))),
+            },
+            "local" if value.is_empty() => builder,
+            "local" => return Err(invalid(format!("the config depends on the {} of the machine it was made on", value))),
+            "dirs" => {
+                for dir in value.split('/').filter(|dir| !dir.is_empty()) {
+                    let mut chars = dir.chars();
+                    let Some(icon) = chars.next() else {
+                        continue;
+                    };
+                    builder = builder.register_dir(icon, unescape_config_value(chars.as_str()).ok_or_else(unknown)?);
+                }
+                builder
+            },
+            _ => return Err(invalid(format!("unknown config key {:?}", key))),
+        };
+    }
+    Ok((builder, escaping_only))
+}
+
+// rejects the configs compressing OS and common dirs in builds that can't
+fn check_escaping_only(escaping_only: bool) -> Result<(), Error> {
+    if !escaping_only && cfg!(not(feature = "platform")) {
+        return Err(Error::InvalidConfig("the config needs the platform feature to compress OS and common dirs, unless it has escaping-only=true".into()));
+    }
+    Ok(())
+}
+
+// the escaper of the config strings in headers, which are escaped like paths of a codec with headers
+fn header_escaper() -> Escaper {
+    Escaper::new(&[(HEADER_ICON, HEADER_ESCAPED_ICON)], &[], false, None)
+}
+
+// splits a filename into the config string of its header and the rest
+pub(crate) fn split_header(filename: &str) -> Result<Option<(String, &str)>, Error> {
+    let Some(rest) = filename.strip_prefix(HEADER_ICON) else {
+        return Ok(None);
+    };
+    let Some((header, rest)) = rest.split_once(HEADER_ICON) else {
+        return Err(Error::ParseError(nom::error::Error { input: filename.to_string(), code: nom::error::ErrorKind::Tag }));
+    };
+    let escaper = header_escaper();
+    let (i, config) = escaper.unescape(header)?;
+    if !i.is_empty() {
+        return Err(Error::ParseError(nom::error::Error { input: i.to_string(), code: nom::error::ErrorKind::Eof }));
+    }
+    Ok(Some((config, rest)))
+}
+
+const SPACES: &[(SpaceReplacement, &str)] = &[
+    (SpaceReplacement::Keep, "keep"),
+    (SpaceReplacement::OpenBox, "open-box"),
+    (SpaceReplacement::NoBreakSpace, "no-break-space"),
+    (SpaceReplacement::IdeographicSpace, "ideographic-space"),
+];
+
+const TRAILING_SEPARATORS: &[(TrailingSeparator, &str)] = &[
+    (TrailingSeparator::Preserve, "preserve"),
+    (TrailingSeparator::Strip, "strip"),
+    (TrailingSeparator::Mark, "mark"),
+];
+
+#[cfg(feature = "platform")]
+const DRIVE_LETTER_CASES: &[(DriveLetterCase, &str)] = &[
+    (DriveLetterCase::Preserve, "preserve"),
+    (DriveLetterCase::Uppercase, "uppercase"),
+];
+
+#[cfg(feature = "platform")]
+const CASE_RESTORATIONS: &[(CaseRestoration, &str)] = &[
+    (CaseRestoration::Original, "original"),
+    (CaseRestoration::Canonical, "canonical"),
+];
+
+#[cfg(feature = "platform")]
+const UNKNOWN_ICON_POLICIES: &[(UnknownIconPolicy, &str)] = &[
+    (UnknownIconPolicy::PassThrough, "pass-through"),
+    (UnknownIconPolicy::Error, "error"),
+    (UnknownIconPolicy::Strip, "strip"),
+];
+
+#[cfg(feature = "platform")]
+const COMMON_DIRS: &[(CommonDir, &str)] = &[
+    (CommonDir::Home, "home"),
+    (CommonDir::Music, "music"),
+    (CommonDir::AppData, "app-data"),
+    (CommonDir::Desktop, "desktop"),
+    (CommonDir::Documents, "documents"),
+    (CommonDir::Downloads, "downloads"),
+    (CommonDir::Pictures, "pictures"),
+    (CommonDir::Videos, "videos"),
+    (CommonDir::Caches, "caches"),
+    (CommonDir::Drive, "drive"),
+];
+
+fn name_of<T: PartialEq>(value: T, names: &[(T, &'static str)]) -> &'static str {
+    // every value is in the tables, which the tests check
+    names.iter().find(|(v, _)| *v == value).map_or("", |(_, name)| *name)
+}
+
+fn parse_name<T: Copy>(name: &str, names: &[(T, &str)]) -> Option<T> {
+    names.iter().find(|(_, n)| *n == name).map(|(value, _)| *value)
+}
+
+fn spaces_name(spaces: SpaceReplacement) -> &'static str {
+    name_of(spaces, SPACES)
+}
+
+fn trailing_separator_name(trailing_separator: TrailingSeparator) -> &'static str {
+    name_of(trailing_separator, TRAILING_SEPARATORS)
+}
+
+#[cfg(feature = "platform")]
+fn drive_letter_case_name(drive_letter_case: DriveLetterCase) -> &'static str {
+    name_of(drive_letter_case, DRIVE_LETTER_CASES)
+}
+
+#[cfg(feature = "platform")]
+fn case_restoration_name(case_restoration: CaseRestoration) -> &'static str {
+    name_of(case_restoration, CASE_RESTORATIONS)
+}
+
+#[cfg(feature = "platform")]
+fn unknown_icon_policy_name(unknown_icons: UnknownIconPolicy) -> &'static str {
+    name_of(unknown_icons, UNKNOWN_ICON_POLICIES)
+}
+
+// percent-escapes the chars delimiting the config string, where a registered dir name can't contain `/`
+fn escape_config_value(value: &str) -> String {
+    let mut r = String::new();
+    for c in value.chars() {
+        match c {
+            '%' | ';' | '=' => r.push_str(&format!("%{:02X}", c as u32)),
+            c => r.push(c),
+        }
+    }
+    r
+}
+
+fn unescape_config_value(value: &str) -> Option<String> {
+    let mut r = String::new();
+    let mut rest = value;
+    while let Some(n) = rest.find('%') {
+        r.push_str(rest.get(..n)?);
+        let hex = rest.get(n + 1..n + 3)?;
+        r.push(u8::from_str_radix(hex, 16).ok()? as char);
+        rest = rest.get(n + 3..)?;
+    }
+    r.push_str(rest);
+    Some(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_string() {
+        let default = format!("v1;spaces=keep;trailing=preserve;collapse=false;links=false;compact=false;translit=;header=false;escaping-only={};drive=preserve;case=original;unknown=pass-through;uncompressed=;local=;dirs=", cfg!(not(feature = "platform")));
+        assert_eq!(Codec::new().to_config_string(), default);
+
+        let codec = Codec::builder()
+            .spaces(SpaceReplacement::OpenBox)
+            .trailing_separator(TrailingSeparator::Mark)
+            .collapse_separators(true)
+            .escaping_only(true)
+            .register_dir('🚧', "Projects")
+            .register_dir('🧪', "a;b=100%")
+            .build().unwrap();
+        let config = codec.to_config_string();
+        assert_eq!(config, "v1;spaces=open-box;trailing=mark;collapse=true;links=false;compact=false;translit=;header=false;escaping-only=true;drive=preserve;case=original;unknown=pass-through;uncompressed=;local=;dirs=🚧Projects/🧪a%3Bb%3D100%25");
+        let restored = Codec::from_config_string(&config).unwrap();
+        assert_eq!(restored.to_config_string(), config);
+        assert_eq!(restored.to_filename("/tmp/my file/"), codec.to_filename("/tmp/my file/"));
+
+        #[cfg(feature = "platform")]
+        assert_eq!(Codec::from_config_string("v1").unwrap().to_config_string(), default);
+        #[cfg(not(feature = "platform"))]
+        assert_eq!(
+            Codec::from_config_string("v1;spaces=keep").err(),
+            Some(Error::InvalidConfig("the config needs the platform feature to compress OS and common dirs, unless it has escaping-only=true".into())),
+        );
+        assert_eq!(Codec::from_config_string("v1;escaping-only=true").unwrap().to_config_string(), default.replace("escaping-only=false", "escaping-only=true"));
+        assert_eq!(Codec::from_config_string("v2;spaces=keep").err(), Some(Error::InvalidConfig("unsupported config version \"v2\"".into())));
+        assert_eq!(Codec::from_config_string("v1;spaces").err(), Some(Error::InvalidConfig("config field \"spaces\" isn't a key=value pair".into())));
+        assert_eq!(Codec::from_config_string("v1;spaces=tab").err(), Some(Error::InvalidConfig("unknown value \"tab\" of the config key \"spaces\"".into())));
+        assert_eq!(Codec::from_config_string("v1;colour=red").err(), Some(Error::InvalidConfig("unknown config key \"colour\"".into())));
+        assert_eq!(Codec::from_config_string("v1;dirs=🚧a%2").err(), Some(Error::InvalidConfig("unknown value \"🚧a%2\" of the config key \"dirs\"".into())));
+        assert_eq!(Codec::from_config_string("v1;dirs=🍎Apps").err(), Some(Error::InvalidConfig("icon 🍎 of the registered dir \"Apps\" is already in use".into())));
+        for icon in [';', '=', '/', '%'] {
+            assert!(Codec::builder().register_dir(icon, "P").build().is_err());
+        }
+
+        // a builder takes the settings config strings don't hold
+        let config = "v1;compact=true;escaping-only=true";
+        let codec = CodecBuilder::from_config_string(config).unwrap().max_input_len(4).build().unwrap();
+        assert_eq!(codec.to_config_string(), Codec::from_config_string(config).unwrap().to_config_string());
+        assert!(matches!(codec.to_path_from_str("／abcd"), Err(Error::LimitExceeded(_))));
+        assert!(CodecBuilder::from_config_string("v1;colour=red").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn headers() {
+        let codec = Codec::builder().header(true).spaces(SpaceReplacement::OpenBox).register_dir('🚧', "a;b").build().unwrap();
+        let filename = codec.to_filename("/tmp/my file").unwrap();
+        assert_eq!(filename, "🧾v1;spaces=open-box;dirs=🚧a%3Bb🧾／tmp／my␣file");
+        assert_eq!(codec.to_path(&filename), Ok("/tmp/my file".into()));
+        assert_eq!(decode_auto(&filename), Ok("/tmp/my file".into()));
+        assert_eq!(Codec::from_config_string(codec.to_config_string()).unwrap().to_filename("/tmp/my file"), Ok(filename.clone()));
+        assert_eq!(codec.to_path("／tmp／a"), Ok("/tmp/a".into()));
+        assert_eq!(decode_auto("／tmp／a"), Ok("/tmp/a".into()));
+
+        let default = Codec::builder().header(true).build().unwrap();
+        assert_eq!(default.to_filename("/tmp"), Ok("🧾v1🧾／tmp".into()));
+        let header_error = |input: &str| Err(Error::ParseError(nom::error::Error { input: input.into(), code: nom::error::ErrorKind::Tag }));
+        assert_eq!(default.to_path(&filename), header_error(&filename));
+        assert_eq!(decode_auto("🧾v1x"), Ok("🧾v1x".into()));
+        assert_eq!(decode_auto("🧾v2🧾x"), Err(Error::InvalidConfig("unsupported config version \"v2\"".into())));
+
+        // a literal header icon is escaped, only by codecs with headers
+        assert_eq!(default.to_filename("🧾x📜"), Ok("🧾v1🧾📜x📜📜".into()));
+        assert_eq!(default.to_path("🧾v1🧾📜x📜📜"), Ok("🧾x📜".into()));
+        assert_eq!(decode_auto("🧾v1🧾📜x📜📜"), Ok("🧾x📜".into()));
+        for path in ["/🧾x📜", "/tmp/📜", "🧾", "/a🧾b🧾"] {
+            assert_eq!(decode_auto(default.to_filename(path).unwrap()), Ok(path.into()), "{}", path);
+            assert_eq!(codec.decode_auto(codec.to_filename(path).unwrap()), Ok(path.into()), "{}", path);
+        }
+        assert_eq!(to_filename("🧾x📜"), Ok("🧾x📜".into()));
+        assert_eq!(to_path("🧾v1🧾📜x"), Ok("🧾v1🧾📜x".into()));
+        let codec = Codec::builder().header(true).register_dir('🚧', "a🧾b").build().unwrap();
+        assert_eq!(decode_auto(codec.to_filename("/x").unwrap()), Ok("/x".into()));
+    }
+
+    // the headers of escaping only codecs read the same with and without the platform feature
+    #[test]
+    fn escaping_only_headers() {
+        let codec = Codec::builder().header(true).escaping_only(true).spaces(SpaceReplacement::OpenBox).build().unwrap();
+        let filename = codec.to_filename("/Users/alice/my file").unwrap();
+        assert_eq!(filename, "🧾v1;spaces=open-box;escaping-only=true🧾／Users／alice／my␣file");
+        assert_eq!(decode_auto(&filename), Ok("/Users/alice/my file".into()));
+        assert_eq!(decode_auto("🧾v1;escaping-only=true🧾🍎📄alice"), Ok("🍎📄alice".into()));
+        assert_eq!(Codec::from_config_string(codec.to_config_string()).unwrap().to_filename("/Users/alice/my file"), Ok(filename));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn platform_config_string() {
+        let codec = Codec::builder()
+            .drive_letter_case(DriveLetterCase::Uppercase)
+            .case_restoration(CaseRestoration::Canonical)
+            .unknown_icons(UnknownIconPolicy::Strip)
+            .compress_dir(CommonDir::Downloads, false)
+            .compress_dir(CommonDir::Music, false)
+            .build().unwrap();
+        let config = codec.to_config_string();
+        assert_eq!(config, "v1;spaces=keep;trailing=preserve;collapse=false;links=false;compact=false;translit=;header=false;escaping-only=false;drive=uppercase;case=canonical;unknown=strip;uncompressed=music/downloads;local=;dirs=");
+        let restored = Codec::from_config_string(&config).unwrap();
+        assert_eq!(restored.to_filename("c:\\x"), Ok("💠🔡🥞C＼x".into()));
+        assert_eq!(restored.to_path("💠🔡🥞C＼x"), Ok("C:\\x".into()));
+        assert_eq!(restored.to_filename("/Users/alice/Downloads/x"), Ok("🍎🏠alice／Downloads／x".into()));
+        assert_eq!(restored.to_path("🦀x"), Ok("x".into()));
+        assert_eq!(Codec::from_config_string("v1;uncompressed=trash").err(), Some(Error::InvalidConfig("unknown value \"trash\" of the config key \"uncompressed\"".into())));
+    }
+}
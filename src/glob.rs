@@ -0,0 +1,199 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use super::*;
+
+/// A glob over original paths, to find the encoded artifacts of some of them by what they decode to.
+///
+/// `*` matches any chars within a path component, `?` one char, `[a-z]` and `[!a-z]` one char of or not of a
+/// set, and `**` any chars including separators, where `**/` also matches no component at all, so `/src/**/*.rs`
+/// matches `/src/lib.rs`. Both `/` and `\` are separators, in the pattern and in paths alike, so one pattern
+/// works for paths of any platform. Other chars match themselves, case-sensitively.
+///
+/// ```rust
+/// use path_to_unicode_filename::*;
+///
+/// let glob = Glob::new("/tmp/**/*.txt").unwrap();
+/// assert!(glob.matches("/tmp/a.txt"));
+/// assert!(glob.matches("/tmp/a/b/c.txt"));
+/// assert!(!glob.matches("/var/a.txt"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    Separator,
+    AnyChar,
+    Class { negated: bool, ranges: Vec<(char, char)> },
+    // `*`
+    AnyChars,
+    // `**` not followed by a separator
+    AnyPath,
+    // `**/`, any leading components including none
+    AnyDirs,
+}
+
+impl Glob {
+    /// Parses a pattern, which is [`Error::InvalidGlob`] only if a `[` isn't closed.
+    pub fn new(pattern: impl AsRef<str>) -> Result<Self, Error> {
+        let pattern = pattern.as_ref();
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '/' | '\\' => Token::Separator,
+                '?' => Token::AnyChar,
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.next_if(|&c| c == '/' || c == '\\').is_some() { Token::AnyDirs } else { Token::AnyPath }
+                },
+                '*' => Token::AnyChars,
+                '[' => {
+                    let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                    let mut ranges = Vec::new();
+                    // a `]` first is a member, like in `[]a]`
+                    let mut first = true;
+                    loop {
+                        let Some(c) = chars.next() else {
+                            return Err(Error::InvalidGlob(format!("{:?} has an unclosed [", pattern)));
+                        };
+                        if c == ']' && !first {
+                            break;
+                        }
+                        first = false;
+                        match chars.peek() {
+                            Some('-') => {
+                                chars.next();
+                                match chars.next_if(|&c| c != ']') {
+                                    Some(end) => ranges.push((c, end)),
+                                    // a `-` last is a member, like in `[a-]`
+                                    None => ranges.extend([(c, c), ('-', '-')]),
+                                }
+                            },
+                            _ => ranges.push((c, c)),
+                        }
+                    }
+                    Token::Class { negated, ranges }
+                },
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Self { tokens })
+    }
+
+    /// Whether the whole of `path` matches the pattern. Paths that aren't UTF-8 never match.
+    pub fn matches(&self, path: impl AsRef<Path>) -> bool {
+        path.as_ref().to_str().is_some_and(|path| self.matches_str(path))
+    }
+
+    pub fn matches_str(&self, path: impl AsRef<str>) -> bool {
+        let chars: Vec<char> = path.as_ref().chars().collect();
+        let is_sep = |c: char| c == POSIX_SEP || c == WINDOWS_SEP;
+
+        // `matched[j]` is whether the tokens from the current one on match `chars[j..]`, from the last token back
+        let mut matched = vec![false; chars.len() + 1];
+        if let Some(last) = matched.last_mut() {
+            *last = true;
+        }
+        for token in self.tokens.iter().rev() {
+            let next = matched;
+            matched = vec![false; chars.len() + 1];
+            // for `AnyDirs`, whether a separator at or after `j` is followed by a match
+            let mut dirs_then_next = false;
+            for j in (0..=chars.len()).rev() {
+                let c = chars.get(j).copied();
+                let then_next = next.get(j + 1).copied().unwrap_or(false);
+                let here = match token {
+                    Token::Char(expected) => c == Some(*expected) && then_next,
+                    Token::Separator => c.is_some_and(is_sep) && then_next,
+                    Token::AnyChar => c.is_some_and(|c| !is_sep(c)) && then_next,
+                    Token::Class { negated, ranges } => {
+                        c.is_some_and(|c| !is_sep(c) && ranges.iter().any(|&(start, end)| start <= c && c <= end) != *negated) && then_next
+                    },
+                    Token::AnyChars => next[j] || (c.is_some_and(|c| !is_sep(c)) && matched.get(j + 1).copied().unwrap_or(false)),
+                    Token::AnyPath => next[j] || (c.is_some() && matched.get(j + 1).copied().unwrap_or(false)),
+                    Token::AnyDirs => {
+                        dirs_then_next = dirs_then_next || (c.is_some_and(is_sep) && then_next);
+                        next[j] || dirs_then_next
+                    },
+                };
+                if let Some(m) = matched.get_mut(j) {
+                    *m = here;
+                }
+            }
+        }
+        matched.first().copied().unwrap_or(false)
+    }
+}
+
+impl Codec {
+    /// Reads the encoded artifacts in `dir` and yields the `(artifact_path, decoded_original_path)` pairs of
+    /// the ones whose originals `glob` matches.
+    ///
+    /// Like [`Codec::decode_dir`], entries that can't be read or decoded are reported as errors, since they may
+    /// or may not have matched.
+    pub fn decode_dir_matching(&self, dir: impl AsRef<Path>, glob: &Glob) -> impl Iterator<Item = Result<(PathBuf, PathBuf), (PathBuf, Error)>> {
+        let glob = glob.clone();
+        self.decode_dir(dir).filter(move |entry| entry.as_ref().map_or(true, |(_, original)| glob.matches(original)))
+    }
+}
+
+/// Reads the encoded artifacts in `dir` whose originals `glob` matches.
+///
+/// See [`Codec::decode_dir_matching`].
+pub fn decode_dir_matching(dir: impl AsRef<Path>, glob: &Glob) -> impl Iterator<Item = Result<(PathBuf, PathBuf), (PathBuf, Error)>> {
+    Codec::new().decode_dir_matching(dir, glob)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn glob() {
+        let matches = |pattern: &str, path: &str| Glob::new(pattern).unwrap().matches_str(path);
+        assert!(matches("/tmp/*.txt", "/tmp/a.txt"));
+        assert!(!matches("/tmp/*.txt", "/tmp/a/b.txt"));
+        assert!(matches("/tmp/**/*.txt", "/tmp/a.txt"));
+        assert!(matches("/tmp/**/*.txt", "/tmp/a/b/c.txt"));
+        assert!(matches("/tmp/**", "/tmp/a/b"));
+        assert!(!matches("/tmp/**", "/var/a"));
+        assert!(matches("**/b", "/a/b"));
+        assert!(matches("/a**", "/a/b"));
+        assert!(matches("/?.t?t", "/a.txt"));
+        assert!(!matches("/?", "/ab"));
+        assert!(matches("/[a-c][!a-c]", "/bd"));
+        assert!(!matches("/[a-c][!a-c]", "/bb"));
+        assert!(matches("/[]*-]", "/]") && matches("/[]*-]", "/*") && matches("/[]*-]", "/-"));
+        assert!(matches("C:/Users/*/x", "C:\\Users\\alice\\x"));
+        assert!(!matches("/tmp/a", "/TMP/a"));
+        assert!(matches("", ""));
+        assert_eq!(Glob::new("/tmp/[a"), Err(Error::InvalidGlob("\"/tmp/[a\" has an unclosed [".into())));
+    }
+
+    #[test]
+    fn dir_matching() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        for path in ["/tmp/a.txt", "/tmp/b.png", "/var/c.txt"] {
+            fs::write(dir.join(to_filename(path).unwrap()), "").unwrap();
+        }
+        fs::write(dir.join("🍎invalid"), "").unwrap();
+
+        let glob = Glob::new("/tmp/*.txt").unwrap();
+        let mut entries: Vec<_> = decode_dir_matching(&dir, &glob).collect();
+        entries.sort_by_key(|entry| entry.is_ok());
+        #[cfg(feature = "platform")]
+        assert!(matches!(entries.remove(0), Err((path, Error::ParseError(_))) if path == dir.join("🍎invalid")));
+        assert_eq!(entries.into_iter().map(Result::unwrap).collect::<Vec<_>>(), [(dir.join("／tmp／a.txt"), PathBuf::from("/tmp/a.txt"))]);
+    }
+}
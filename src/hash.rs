@@ -0,0 +1,147 @@
+use std::{
+    fmt::Write,
+    path::Path,
+};
+
+use super::*;
+
+/// Hashes encoded filenames for the outputs of this crate that need a fixed-size digest.
+///
+/// Implementations must return the same bytes for the same input across versions and platforms, since the
+/// digests end up in filenames and database keys that outlive the process producing them. A keyed hash can
+/// be plugged in by implementing this trait.
+pub trait NameHasher {
+    fn hash(&self, name: &str) -> Vec<u8>;
+}
+
+impl<T: NameHasher + ?Sized> NameHasher for &T {
+    fn hash(&self, name: &str) -> Vec<u8> {
+        (**self).hash(name)
+    }
+}
+
+impl Codec {
+    /// Encodes `path` and returns the filename together with a token for database keys, the lowercase hex of
+    /// the `hasher` digest of the filename.
+    ///
+    /// Both come from the same encoding of the path, so they never disagree, like when one side collapses
+    /// separators and the other doesn't. The token is as short as the digest, e.g. 16 chars with
+    /// `Xxh3Hasher`, and as stable as the filename and the hasher are.
+    pub fn to_filename_and_token(&self, path: impl AsRef<Path>, hasher: impl NameHasher) -> Result<(String, String), Error> {
+        let filename = self.to_filename(path)?;
+        let mut token = String::new();
+        for byte in hasher.hash(&filename) {
+            let _ = write!(token, "{:02x}", byte);
+        }
+        Ok((filename, token))
+    }
+}
+
+/// Encodes `path` and returns the filename with a token for database keys hashed from it.
+///
+/// See [`Codec::to_filename_and_token`].
+pub fn to_filename_and_token(path: impl AsRef<Path>, hasher: impl NameHasher) -> Result<(String, String), Error> {
+    Codec::new().to_filename_and_token(path, hasher)
+}
+
+/// BLAKE3 with the default 32 byte output, optionally keyed.
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Default)]
+pub struct Blake3Hasher {
+    key: Option<[u8; 32]>,
+}
+
+#[cfg(feature = "blake3")]
+impl Blake3Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn keyed(key: [u8; 32]) -> Self {
+        Self { key: Some(key) }
+    }
+}
+
+#[cfg(feature = "blake3")]
+impl NameHasher for Blake3Hasher {
+    fn hash(&self, name: &str) -> Vec<u8> {
+        let hash = match &self.key {
+            Some(key) => blake3::keyed_hash(key, name.as_bytes()),
+            None => blake3::hash(name.as_bytes()),
+        };
+        hash.as_bytes().to_vec()
+    }
+}
+
+/// 64 bit XXH3 with a seed, in big endian bytes. It's fast but not keyed, so it isn't fit for hiding names.
+#[cfg(feature = "xxh3")]
+#[derive(Debug, Clone, Default)]
+pub struct Xxh3Hasher {
+    seed: u64,
+}
+
+#[cfg(feature = "xxh3")]
+impl Xxh3Hasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+#[cfg(feature = "xxh3")]
+impl NameHasher for Xxh3Hasher {
+    fn hash(&self, name: &str) -> Vec<u8> {
+        xxhash_rust::xxh3::xxh3_64_with_seed(name.as_bytes(), self.seed).to_be_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct LenHasher;
+
+    impl NameHasher for LenHasher {
+        fn hash(&self, name: &str) -> Vec<u8> {
+            vec![name.len() as u8]
+        }
+    }
+
+    #[test]
+    fn custom_hasher() {
+        assert_eq!(LenHasher.hash("／tmp"), vec![6]);
+        assert_eq!((&LenHasher as &dyn NameHasher).hash("a"), vec![1]);
+    }
+
+    #[test]
+    fn filename_and_token() {
+        assert_eq!(to_filename_and_token("/tmp", LenHasher), Ok(("／tmp".into(), "06".into())));
+        let codec = Codec::builder().collapse_separators(true).build().unwrap();
+        assert_eq!(codec.to_filename_and_token("/tmp//a", &LenHasher), Ok(("／tmp／a".into(), "0a".into())));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let path = Path::new(std::ffi::OsStr::from_bytes(b"/tmp/\xff"));
+            assert!(matches!(to_filename_and_token(path, LenHasher), Err(Error::CouldntEncodeToUtf8(_))));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "blake3")]
+    fn blake3() {
+        assert_eq!(Blake3Hasher::new().hash("")[..4], [0xaf, 0x13, 0x49, 0xb9]);
+        assert_ne!(Blake3Hasher::keyed([1; 32]).hash(""), Blake3Hasher::new().hash(""));
+        assert_eq!(Blake3Hasher::keyed([1; 32]).hash("／tmp"), Blake3Hasher::keyed([1; 32]).hash("／tmp"));
+    }
+
+    #[test]
+    #[cfg(feature = "xxh3")]
+    fn xxh3() {
+        assert_eq!(Xxh3Hasher::new().hash(""), 0x2d06800538d394c2u64.to_be_bytes());
+        assert_ne!(Xxh3Hasher::with_seed(1).hash(""), Xxh3Hasher::new().hash(""));
+        assert_eq!(to_filename_and_token("/tmp", Xxh3Hasher::new()).map(|(_, token)| token.len()), Ok(16));
+    }
+}
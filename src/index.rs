@@ -0,0 +1,177 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use rusqlite::{
+    params,
+    Connection,
+    OptionalExtension,
+};
+
+use super::*;
+
+/// A persistent SQLite table of original paths and their encoded names, to find the artifact of a source path
+/// in a large store without listing and decoding every filename.
+///
+/// The pairs are stored with the [`Codec::to_config_string`] of the codec that encoded them, without the
+/// settings of the defaults, like `v1;compact=true`, and the lookups only see the pairs of the codec the index is
+/// opened with. So artifacts of differently set up codecs can share one database, and the pairs stay visible
+/// when a release adds a setting. Codecs with settings depending on the machine, like
+/// [`CodecBuilder::mount_table`], are [`Error::InvalidConfig`], since their config strings don't tell them apart.
+pub struct PathIndex {
+    connection: Connection,
+    codec: Codec,
+    config: String,
+}
+
+impl PathIndex {
+    /// Opens the database at `path`, creating it and its table if missing.
+    pub fn open(path: impl AsRef<Path>, codec: Codec) -> Result<Self, Error> {
+        Self::with_connection(Connection::open(path)?, codec)
+    }
+
+    pub fn open_in_memory(codec: Codec) -> Result<Self, Error> {
+        Self::with_connection(Connection::open_in_memory()?, codec)
+    }
+
+    fn with_connection(connection: Connection, codec: Codec) -> Result<Self, Error> {
+        let local = codec.local_settings();
+        if !local.is_empty() {
+            return Err(Error::InvalidConfig(format!("pairs can't be keyed on the {} of this machine", local.join(" and "))));
+        }
+        connection.execute_batch("
+            CREATE TABLE IF NOT EXISTS path_to_unicode_filename (
+                original TEXT NOT NULL,
+                config TEXT NOT NULL,
+                encoded TEXT NOT NULL,
+                PRIMARY KEY (original, config)
+            );
+            CREATE INDEX IF NOT EXISTS path_to_unicode_filename_encoded ON path_to_unicode_filename (encoded, config);
+        ")?;
+        let config = codec.minimal_config_string();
+        Ok(Self { connection, codec, config })
+    }
+
+    pub fn codec(&self) -> &Codec {
+        &self.codec
+    }
+
+    /// Encodes `original`, stores the pair, and returns the encoded name.
+    pub fn upsert(&self, original: impl AsRef<Path>) -> Result<String, Error> {
+        let original = original.as_ref();
+        let encoded = self.codec.to_filename(original)?;
+        self.upsert_encoded(original, &encoded)?;
+        Ok(encoded)
+    }
+
+    /// Stores `original` with an `encoded` name chosen by the caller, e.g. the one with a counter suffix
+    /// [`Codec::to_unique_filename_in`] returned, replacing the name stored before.
+    pub fn upsert_encoded(&self, original: impl AsRef<Path>, encoded: impl AsRef<str>) -> Result<(), Error> {
+        self.connection.execute(
+            "INSERT INTO path_to_unicode_filename (original, config, encoded) VALUES (?1, ?2, ?3)
+                ON CONFLICT (original, config) DO UPDATE SET encoded = excluded.encoded",
+            params![path_to_str(original.as_ref())?, self.config, encoded.as_ref()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the encoded name stored for `original`.
+    pub fn encoded(&self, original: impl AsRef<Path>) -> Result<Option<String>, Error> {
+        let encoded = self.connection.query_row(
+            "SELECT encoded FROM path_to_unicode_filename WHERE original = ?1 AND config = ?2",
+            params![path_to_str(original.as_ref())?, self.config],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(encoded)
+    }
+
+    /// Returns the original path stored for `encoded`.
+    pub fn original(&self, encoded: impl AsRef<str>) -> Result<Option<PathBuf>, Error> {
+        let original: Option<String> = self.connection.query_row(
+            "SELECT original FROM path_to_unicode_filename WHERE encoded = ?1 AND config = ?2",
+            params![encoded.as_ref(), self.config],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(original.map(PathBuf::from))
+    }
+
+    /// Removes the pair of `original` and returns whether there was one.
+    pub fn remove(&self, original: impl AsRef<Path>) -> Result<bool, Error> {
+        let count = self.connection.execute(
+            "DELETE FROM path_to_unicode_filename WHERE original = ?1 AND config = ?2",
+            params![path_to_str(original.as_ref())?, self.config],
+        )?;
+        Ok(0 < count)
+    }
+
+    /// Returns the number of pairs stored for this codec.
+    pub fn len(&self) -> Result<usize, Error> {
+        let count: i64 = self.connection.query_row(
+            "SELECT COUNT(*) FROM path_to_unicode_filename WHERE config = ?1",
+            params![self.config],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+}
+
+fn path_to_str(path: &Path) -> Result<&str, Error> {
+    path.to_str().ok_or_else(|| Error::CouldntEncodeToUtf8(path.as_os_str().to_os_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upsert_and_lookup() {
+        let index = PathIndex::open_in_memory(Codec::new()).unwrap();
+        assert!(index.is_empty().unwrap());
+        assert_eq!(index.upsert("/tmp/a.txt"), Ok("／tmp／a.txt".into()));
+        assert_eq!(index.encoded("/tmp/a.txt"), Ok(Some("／tmp／a.txt".into())));
+        assert_eq!(index.original("／tmp／a.txt"), Ok(Some("/tmp/a.txt".into())));
+        assert_eq!(index.encoded("/tmp/b.txt"), Ok(None));
+        assert_eq!(index.original("／tmp／b.txt"), Ok(None));
+
+        index.upsert_encoded("/tmp/a.txt", "／tmp／a.txt🔢1").unwrap();
+        assert_eq!(index.encoded("/tmp/a.txt"), Ok(Some("／tmp／a.txt🔢1".into())));
+        assert_eq!(index.original("／tmp／a.txt"), Ok(None));
+        assert_eq!(index.len(), Ok(1));
+
+        assert_eq!(index.remove("/tmp/a.txt"), Ok(true));
+        assert_eq!(index.remove("/tmp/a.txt"), Ok(false));
+        assert!(index.is_empty().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "mounts")]
+    fn machine_dependent_codec() {
+        let codec = Codec::builder().mount_table("nas:/export /mnt/music nfs4 rw 0 0").build().unwrap();
+        assert_eq!(PathIndex::open_in_memory(codec).err(), Some(Error::InvalidConfig("pairs can't be keyed on the mounts of this machine".into())));
+    }
+
+    #[test]
+    fn persisted_per_codec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.sqlite");
+        let compact = || Codec::builder().compact_escapes(true).build().unwrap();
+
+        PathIndex::open(&path, Codec::new()).unwrap().upsert("/tmp/a.txt").unwrap();
+        PathIndex::open(&path, compact()).unwrap().upsert("/tmp/a.txt").unwrap();
+
+        // the pairs are keyed on the settings differing from the defaults
+        let index = PathIndex::open(&path, Codec::new()).unwrap();
+        let configs: Vec<String> = index.connection.prepare("SELECT config FROM path_to_unicode_filename ORDER BY config").unwrap()
+            .query_map([], |row| row.get(0)).unwrap().map(Result::unwrap).collect();
+        assert_eq!(configs, ["v1", "v1;compact=true"]);
+        assert_eq!(index.encoded("/tmp/a.txt"), Ok(Some("／tmp／a.txt".into())));
+        assert_eq!(index.len(), Ok(1));
+        assert_eq!(index.remove("/tmp/a.txt"), Ok(true));
+        assert_eq!(PathIndex::open(&path, compact()).unwrap().len(), Ok(1));
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+    },
+};
+
+/// The counts of the interner set up with [`CodecBuilder::intern_tokens`](crate::CodecBuilder::intern_tokens).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct InternStats {
+    /// Usernames and volumes whose escaped form was shared instead of escaped again.
+    pub hits: u64,
+    /// Usernames and volumes that were escaped, since they weren't interned yet or the interner was full.
+    pub misses: u64,
+    /// Escaped usernames and volumes held.
+    pub entries: usize,
+}
+
+// a bounded map of usernames and volumes to their escaped forms, shared across threads by the codec clones.
+// when it's full, new ones are escaped every time rather than evicting, since a batch only has a handful.
+#[derive(Debug)]
+pub(crate) struct Interner {
+    capacity: usize,
+    entries: RwLock<HashMap<String, Arc<str>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Interner {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self { capacity, entries: RwLock::new(HashMap::new()), hits: AtomicU64::new(0), misses: AtomicU64::new(0) }
+    }
+
+    pub(crate) fn escaped(&self, token: &str, escape: impl FnOnce(&str) -> String) -> Arc<str> {
+        if let Some(escaped) = self.entries.read().unwrap_or_else(|err| err.into_inner()).get(token) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return escaped.clone();
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let escaped: Arc<str> = escape(token).into();
+
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        if entries.len() < self.capacity {
+            entries.entry(token.to_string()).or_insert_with(|| escaped.clone());
+        }
+        escaped
+    }
+
+    pub(crate) fn stats(&self) -> InternStats {
+        InternStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().unwrap_or_else(|err| err.into_inner()).len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn interner() {
+        let interner = Interner::new(1);
+        assert_eq!(&*interner.escaped("a", |token| token.to_uppercase()), "A");
+        assert_eq!(&*interner.escaped("a", |_| unreachable!()), "A");
+        assert_eq!(&*interner.escaped("b", |token| token.to_uppercase()), "B");
+        assert_eq!(&*interner.escaped("b", |token| token.to_uppercase()), "B");
+        assert_eq!(interner.stats(), InternStats { hits: 1, misses: 3, entries: 1 });
+    }
+
+    #[test]
+    fn interned_codec() {
+        assert_eq!(Codec::new().intern_stats(), None);
+
+        let codec = Codec::builder().intern_tokens(8).build().unwrap();
+        let threads: Vec<_> = (0..4).map(|_| {
+            let codec = codec.clone();
+            thread::spawn(move || {
+                for n in 0..100 {
+                    assert_eq!(codec.to_filename(format!("/Users/a:lice/Documents/{}", n)), Ok(format!("🍎📄a：lice／{}", n)));
+                    assert_eq!(codec.to_filename(format!("D:\\{}", n)), Ok(format!("💠🥞D＼{}", n)));
+                }
+            })
+        }).collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let stats = codec.intern_stats().unwrap();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits + stats.misses, 800);
+        assert!(stats.misses <= 8, "{:?}", stats);
+
+        assert_eq!(codec.to_filename("/tmp/a"), Ok("／tmp／a".into()));
+        assert_eq!(codec.intern_stats().unwrap().hits + codec.intern_stats().unwrap().misses, 800);
+    }
+}
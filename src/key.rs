@@ -0,0 +1,138 @@
+use std::{
+    borrow::Borrow,
+    fmt,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use super::*;
+
+/// An encoded filename to use as a key of `HashMap`s and `BTreeMap`s instead of the path it came from.
+///
+/// Equality, hashing and ordering are the ones of the encoded string, so a map of keys can be looked up with a
+/// `&str` too. The order is the byte order of the UTF-8 string, equal to the order of its code points. The
+/// keys of a codec built from a `v1` config string of [`Codec::to_config_string`] stay the same in later
+/// releases, as the filenames of `tests/golden/v1.tsv` the tests check do, so they can be persisted, e.g. as
+/// sorted index files.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EncodedKey(String);
+
+impl EncodedKey {
+    /// Encodes `path` with `codec`.
+    pub fn new(codec: &Codec, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self(codec.to_filename(path)?))
+    }
+
+    /// Wraps a filename `encoded` before, like a key read back from storage, after checking it decodes.
+    pub fn from_encoded(codec: &Codec, encoded: impl Into<String>) -> Result<Self, Error> {
+        let encoded = encoded.into();
+        codec.to_path_from_str(&encoded)?;
+        Ok(Self(encoded))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Decodes the key back to its path with `codec`, which must be set up as the one that encoded it.
+    pub fn to_path(&self, codec: &Codec) -> Result<PathBuf, Error> {
+        codec.to_path_from_str(&self.0)
+    }
+}
+
+/// Encodes the path with the default codec.
+impl TryFrom<&Path> for EncodedKey {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Error> {
+        Self::new(&Codec::new(), path)
+    }
+}
+
+impl Borrow<str> for EncodedKey {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for EncodedKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<EncodedKey> for String {
+    fn from(key: EncodedKey) -> Self {
+        key.0
+    }
+}
+
+impl fmt::Display for EncodedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{
+        BTreeMap,
+        HashMap,
+    };
+
+    use super::*;
+
+    #[test]
+    fn map_keys() {
+        let key = EncodedKey::try_from(Path::new("/tmp/a.txt")).unwrap();
+        assert_eq!(key.as_str(), "／tmp／a.txt");
+        assert_eq!(key.to_string(), "／tmp／a.txt");
+        assert_eq!(key.to_path(&Codec::new()), Ok("/tmp/a.txt".into()));
+
+        let mut hash_map = HashMap::new();
+        hash_map.insert(key.clone(), 1);
+        assert_eq!(hash_map.get("／tmp／a.txt"), Some(&1));
+
+        let mut btree_map = BTreeMap::new();
+        for path in ["/tmp/b", "/tmp/a", "/tmp/a/b"] {
+            btree_map.insert(EncodedKey::try_from(Path::new(path)).unwrap(), path);
+        }
+        assert_eq!(btree_map.values().copied().collect::<Vec<_>>(), ["/tmp/a", "/tmp/a/b", "/tmp/b"]);
+        assert_eq!(btree_map.get("／tmp／b"), Some(&"/tmp/b"));
+
+        let codec = Codec::builder().spaces(SpaceReplacement::OpenBox).build().unwrap();
+        assert_eq!(EncodedKey::new(&codec, "/tmp/a b").map(String::from), Ok("／tmp／a␣b".into()));
+        assert_eq!(EncodedKey::from_encoded(&codec, "／tmp／a␣b").map(EncodedKey::into_string), Ok("／tmp／a␣b".into()));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn undecodable_key() {
+        assert!(EncodedKey::from_encoded(&Codec::new(), "🍎invalid").is_err());
+    }
+
+    // the filenames the `v1` config strings are pinned to, which a change of the encoding must not touch
+    #[test]
+    fn golden_v1() {
+        for line in include_str!("../tests/golden/v1.tsv").lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<_> = line.split('\t').collect();
+            let [config, path, filename] = fields[..] else {
+                panic!("malformed golden line {:?}", line);
+            };
+            let codec = match Codec::from_config_string(config) {
+                Ok(codec) => codec,
+                // only the escaping only strings are read without the platform feature
+                Err(_) if cfg!(not(feature = "platform")) => continue,
+                Err(err) => panic!("{:?} of {:?}", err, line),
+            };
+            assert_eq!(EncodedKey::new(&codec, path).map(String::from), Ok(filename.into()), "{}", line);
+            assert_eq!(codec.to_path(filename), Ok(path.into()), "{}", line);
+        }
+    }
+}
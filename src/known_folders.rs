@@ -0,0 +1,68 @@
+use std::{
+    ptr,
+    slice,
+};
+
+use windows_sys::{
+    core::GUID,
+    Win32::{
+        System::Com::CoTaskMemFree,
+        UI::Shell::{
+            SHGetKnownFolderPath,
+            FOLDERID_Desktop,
+            FOLDERID_Documents,
+            FOLDERID_Downloads,
+            FOLDERID_LocalAppData,
+            FOLDERID_Music,
+            FOLDERID_Pictures,
+            FOLDERID_Profile,
+            FOLDERID_Videos,
+            KF_FLAG_DEFAULT,
+        },
+    },
+};
+
+use super::*;
+
+// the known folders and where they are relative to the profile by default
+const KNOWN_FOLDERS: [(GUID, &str); 7] = [
+    (FOLDERID_Music, "Music"),
+    (FOLDERID_LocalAppData, "AppData\\Local"),
+    (FOLDERID_Desktop, "Desktop"),
+    (FOLDERID_Documents, "Documents"),
+    (FOLDERID_Downloads, "Downloads"),
+    (FOLDERID_Pictures, "Pictures"),
+    (FOLDERID_Videos, "Videos"),
+];
+
+// the known folders of the current user that aren't at their usual place under `C:\Users\<user>`
+pub(crate) fn relocations() -> Result<Vec<Relocation>, Error> {
+    let profile = known_folder_path(&FOLDERID_Profile).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the user profile isn't found"))?;
+    let user = profile.rsplit(WINDOWS_SEP).next().unwrap_or(&profile);
+    let home = String::from("C:\\Users\\") + user;
+
+    let mut relocations = vec![Relocation { canonical: home.clone(), actual: profile.clone(), mount: false }];
+    for (id, dir) in KNOWN_FOLDERS {
+        if let Some(actual) = known_folder_path(&id) {
+            relocations.push(Relocation { canonical: format!("{}{}{}", home, WINDOWS_SEP, dir), actual, mount: false });
+        }
+    }
+    relocations.retain(|relocation| !relocation.canonical.eq_ignore_ascii_case(&relocation.actual));
+    Ok(relocations)
+}
+
+fn known_folder_path(id: &GUID) -> Option<String> {
+    let mut path = ptr::null_mut();
+    // SAFETY: the returned buffer is a nul-terminated UTF-16 string owned by us until freed, even on failure
+    unsafe {
+        let result = SHGetKnownFolderPath(id, KF_FLAG_DEFAULT as u32, ptr::null_mut(), &mut path);
+        let decoded = if result == 0 && !path.is_null() {
+            let len = (0..).take_while(|&n| *path.add(n) != 0).count();
+            String::from_utf16(slice::from_raw_parts(path, len)).ok()
+        } else {
+            None
+        };
+        CoTaskMemFree(path as *const _);
+        decoded
+    }
+}
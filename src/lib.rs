@@ -4,12 +4,14 @@
 //! 
 //! - chars `\/:*?"<>|` be replaced to full width alternative chars of unicode.
 //! - U+0000 be replaced to `〇`.
+//! - ASCII whitespace chars other than space (`\t`, `\n`, `\r`, etc.) be replaced to visible alternative chars (`⏩`, `〷`, `🔙`, etc).
 //! - a common directory, like home, documents, pictures, etc are replaced to a OS icon (🍎, 🐧, etc) and a directory icon (🏠, 📄, 🎨, etc).
 //! - chars replacements for others be replaced to twice-sequential chars itself
 //! 
 //! # Examples
 //! 
 //! ```rust
+//! # #[cfg(feature = "platform")] {
 //! use path_to_unicode_filename::*;
 //!
 //! // make a filename
@@ -21,13 +23,119 @@
 //! assert_eq!(to_path("／var／log／file.txt"), Ok("/var/log/file.txt".into()));
 //! assert_eq!(to_path("🐧🥞sdcard001／file.txt"), Ok("/media/sdcard001/file.txt".into()));
 //! assert_eq!(to_path("🍎🎨bob／file.png"), Ok("/Users/bob/Pictures/file.png".into()));
+//! # }
 //! ```
 //!
+//! # Features
+//!
+//! - `platform` (default): the OS icons and common directory compression. Without it, paths are only escaped char
+//!   by char, e.g. `/Users/alice` to `／Users／alice`, and icons in filenames are decoded as literal chars.
+//!   **The feature isn't additive**: the default codecs of builds with and without it encode the same path to
+//!   different filenames, and Cargo turns it on for the whole build once any crate depends on this one with
+//!   default features. Filenames that must read the same in every build are made with
+//!   [`CodecBuilder::escaping_only`].
+//! - `short-names`: on Windows, `CodecBuilder::expand_short_names` to resolve 8.3 short names like `PROGRA~1`,
+//!   which [`is_short_name`] detects on any OS.
+//! - `mounts`: `CodecBuilder::mount_table` to compress network shares and bind mounts like `/media` dirs, and
+//!   on Linux `CodecBuilder::local_mounts` to read them from `/proc/self/mountinfo`.
+//! - `localize`: [`to_path_localized`] to restore paths under the home directory of the current user.
+//! - `known-folders`: `CodecBuilder::local_known_folders` to compress relocated known folders on Windows, a
+//!   no-op on other OSes.
+//! - `cli`: the `ptuf` command, e.g. `ptuf encode /tmp/a` to print the filename of a path, `ptuf explain 🍎📄alice／file.txt` to print the segments of a filename, `ptuf check` to scan a directory of them, `ptuf ls --where '/home/*/src/**'` to list the ones whose originals match a glob, and `ptuf completions` for shell completions. The codec is configured by `--config` with a config string file, or by flags like `--spaces` and `--mount-table`, and `--mode ascii` transliterates while `--mode bounded` makes `encode` print a token of `--hasher` after each filename. There's no anonymized mode, since the filenames decode back to their paths.
+//! - `serde`: [`serde_encoded`] to serialize `PathBuf` fields of structs as their encoded filenames.
+//! - `mirror`: [`Mirror`] to keep a flat directory of encoded copies of a source tree up to date.
+//! - `index`: [`PathIndex`] to look up the artifacts of source paths in SQLite without decoding every filename.
+//! - `blake3`, `xxh3`: [`NameHasher`] implementations, e.g. for [`to_filename_and_token`] returning a filename with a short key hashed from it.
+//!
+//! # Compatibility with 0.1
+//!
+//! 0.2 writes new markers with icons, and escapes them with chars 0.1 kept as they are. So the 0.1 filenames
+//! holding the chars below decode differently in 0.2, and the 0.2 filenames holding them don't decode the same in
+//! 0.1. Other filenames decode the same in both.
+//!
+//! - `🔢`, starting the counter of [`Codec::to_unique_filename_in`], and `🔣`, its escape: `a🔢3` is decoded to
+//!   `a` with the counter 3, and `a🔣3` to `a🔢3`.
+//! - `⏩`, `〷`, `🔽`, `📃` and `🔙`, the escapes of `\t`, `\n`, `\x0b`, `\x0c` and `\r`: `a⏩b` is decoded to
+//!   `a\tb`.
+//! - `📂`, marking a trailing separator with [`TrailingSeparator::Mark`], and `📁`, its escape: `a📂` is
+//!   decoded to `a/`, and `a📁` to `a📂`.
+//! - `📎`, the escape of `🔗`, which [`join_encoded`] joins names with: `a📎` is decoded to `a🔗`.
+//! - `🔁`, the escape of `🔀`, which marks links with [`CodecBuilder::link_notation`]: `a🔁` is decoded to `a🔀`.
+//! - `〔`, opening the compact escapes of [`CodecBuilder::compact_escapes`], which every codec decodes, and
+//!   doubled as its own escape: `〔41〕` is decoded to `A`, and `〔x` is encoded to `〔〔x`.
+//!
+//! # Panics
+//!
+//! The functions encoding, decoding and inspecting strings don't panic, whatever they're given, like
+//! [`to_filename`], [`to_path`], [`decode_auto`], [`parse`], [`suggest_fix`], [`decode_unchecked_report`],
+//! [`join_encoded`] and [`Codec::from_config_string`]. Anything unexpected is returned as an [`Error`], or as
+//! `None` where the function returns an `Option`. The tests check it on every string of up to 3 of the chars the
+//! codecs treat specially, and on arbitrary longer strings with proptest. The functions reading and writing the
+//! filesystem, like [`decode_dir`] and [`Mirror`], return the I/O errors they meet, but aren't covered by those
+//! checks. The exceptions are running out of memory and panics of the caller's own code, like a [`NameHasher`]
+//! or [`Transliterator`] implementation.
+//!
+//! # Performance
+//!
+//! `cargo bench` runs the Criterion benchmarks of encoding and decoding POSIX, Windows, escape-heavy and
+//! non-Latin paths. [`CodecBuilder::collect_stats`] counts the chars escaped, the prefixes compressed and the
+//! bytes in and out of a running codec.
+//!
+
+mod cache;
+mod config;
+mod glob;
+mod hash;
+#[cfg(feature = "platform")]
+mod intern;
+mod key;
+mod parse;
+mod report;
+mod short_names;
+mod stats;
+mod translit;
+mod url;
+#[cfg(feature = "platform")]
+mod platform;
+#[cfg(all(windows, feature = "known-folders"))]
+mod known_folders;
+#[cfg(feature = "mounts")]
+mod mounts;
+#[cfg(feature = "serde")]
+pub mod serde_encoded;
+#[cfg(feature = "mirror")]
+mod mirror;
+#[cfg(feature = "index")]
+mod index;
+
+pub use cache::*;
+pub use glob::*;
+pub use hash::*;
+#[cfg(feature = "platform")]
+pub use intern::*;
+pub use key::*;
+pub use parse::*;
+pub use report::*;
+pub use short_names::*;
+pub use stats::*;
+pub use translit::*;
+pub use url::*;
+#[cfg(feature = "mirror")]
+pub use mirror::*;
+#[cfg(feature = "index")]
+pub use index::*;
+#[cfg(feature = "platform")]
+use platform::*;
 
 use std::{
+    borrow::Cow,
     path::{
+        Component,
+        Components,
         Path,
         PathBuf,
+        Prefix,
+        MAIN_SEPARATOR,
     },
     ffi::{
         OsStr,
@@ -36,6 +144,20 @@ use std::{
     collections::{
         HashMap,
     },
+    fs,
+    io::{
+        self,
+        Write,
+    },
+    process,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
     iter::{
         zip,
     },
@@ -44,21 +166,22 @@ use std::{
 use nom::{
     bytes::{
         complete::{
-            tag,
             take,
-            take_while1,
+            take_till,
         },
     },
     character::{
         complete::{
             char,
-            satisfy,
+            digit1,
+            hex_digit1,
         },
     },
     sequence::{
         preceded,
         terminated,
         delimited,
+        pair,
     },
     branch::{
         alt,
@@ -67,10 +190,12 @@ use nom::{
         eof,
         map,
         recognize,
-        success,
         peek,
-        fail,
         verify,
+        map_res,
+        map_opt,
+        not,
+        opt,
     },
     multi::{
         fold_many0,
@@ -80,6 +205,8 @@ use nom::{
     Needed,
 };
 
+use unicode_normalization::UnicodeNormalization;
+
 const POSIX_SEP: char = '/';
 const WINDOWS_SEP: char = '\\';
 
@@ -87,8 +214,21 @@ const MAC_ICON: char = '🍎';
 const LINUX_ICON: char = '🐧';
 const WINDOWS_ICON: char = '💠';
 
-const ESCAPE_TARGET_CHARS: &str = "\0\\/:*?\"<>|🍎🐧💠";
-const ESCAPED_CHARS: &str = "〇＼／：＊？＂＜＞｜🍏🐤🚪";
+const COUNTER_ICON: char = '🔢';
+const TRAILING_SEP_ICON: char = '📂';
+const LOWERCASE_ROOT_ICON: char = '🔡';
+const JOINER_ICON: char = '🔗';
+const LINK_ICON: char = '🔀';
+const HEADER_ICON: char = '🧾';
+const HEADER_ESCAPED_ICON: char = '📜';
+
+const LINK_NOTATION: &str = " -> ";
+
+const COMPACT_ESCAPE_OPEN: char = '〔';
+const COMPACT_ESCAPE_CLOSE: char = '〕';
+
+const ESCAPE_TARGET_CHARS: &str = "\0\\/:*?\"<>|\t\n\x0b\x0c\r🍎🐧💠🔢📂🔗🔀";
+const ESCAPED_CHARS: &str = "〇＼／：＊？＂＜＞｜⏩〷🔽📃🔙🍏🐤🚪🔣📁📎🔁";
 
 const HOME_ICON: char = '🏠';
 const MUSIC_ICON: char = '🎵';
@@ -98,13 +238,74 @@ const DOCUMENTS_ICON: char = '📄';
 const DOWNLOADS_ICON: char = '⏬';
 const PICTURES_ICON: char = '🎨';
 const VIDEOS_ICON: char = '🎥';
+const CACHES_ICON: char = '📦';
 const DRIVE_ICON: char = '🥞';
 
-#[derive(Debug, PartialEq)]
+const RESERVED_ICONS: [char; 16] = [
+    MAC_ICON, LINUX_ICON, WINDOWS_ICON, COUNTER_ICON, TRAILING_SEP_ICON, LOWERCASE_ROOT_ICON,
+    HOME_ICON, MUSIC_ICON, APP_DATA_ICON, DESKTOP_ICON, DOCUMENTS_ICON, DOWNLOADS_ICON, PICTURES_ICON, VIDEOS_ICON, CACHES_ICON, DRIVE_ICON,
+];
+
+// the chars of the emoji and symbol blocks, which look like icons
+fn is_icon_like(c: char) -> bool {
+    matches!(c, '\u{2300}'..='\u{23FF}' | '\u{2600}'..='\u{27BF}' | '\u{2B00}'..='\u{2BFF}' | '\u{1F000}'..='\u{1FAFF}')
+}
+
+#[derive(Debug)]
 pub enum Error {
     CouldntEncodeToUtf8(OsString),
     ParseError(nom::error::Error<String>),
     IncompleteStream(Needed),
+    Io(io::Error),
+    InvalidConfig(String),
+    /// A filename is over one of the limits of the decoder, like [`CodecBuilder::max_input_len`].
+    LimitExceeded(String),
+    /// A URL isn't a `file:` URL of an absolute path, or a path can't be written as one.
+    InvalidUrl(String),
+    /// A pattern of [`Glob::new`] isn't valid.
+    InvalidGlob(String),
+    #[cfg(feature = "mirror")]
+    Watch(notify::Error),
+    #[cfg(feature = "index")]
+    Sqlite(rusqlite::Error),
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::CouldntEncodeToUtf8(a), Error::CouldntEncodeToUtf8(b)) => a == b,
+            (Error::ParseError(a), Error::ParseError(b)) => a == b,
+            (Error::IncompleteStream(a), Error::IncompleteStream(b)) => a == b,
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            (Error::InvalidConfig(a), Error::InvalidConfig(b)) => a == b,
+            (Error::LimitExceeded(a), Error::LimitExceeded(b)) => a == b,
+            (Error::InvalidUrl(a), Error::InvalidUrl(b)) => a == b,
+            (Error::InvalidGlob(a), Error::InvalidGlob(b)) => a == b,
+            #[cfg(feature = "index")]
+            (Error::Sqlite(a), Error::Sqlite(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "mirror")]
+impl From<notify::Error> for Error {
+    fn from(err: notify::Error) -> Self {
+        Error::Watch(err)
+    }
+}
+
+#[cfg(feature = "index")]
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
 }
 
 impl<T> From<Err<nom::error::Error<T>>> for Error
@@ -121,256 +322,89 @@ where
     }
 }
 
-type ParseResult<'a, T = &'a str> = IResult<&'a str, T, nom::error::Error<&'a str>>;
-
-struct Platform {
-    prefix: char,
-    sep: char,
-    parse_sep: fn(i: &str) -> ParseResult,
-    home_dir: fn(user: &str) -> String,
-    parse_home_dir: fn(i: &str) -> ParseResult,
-    drive_dir: fn(volume: &str) -> String,
-    parse_drive_dir: fn(i: &str) -> ParseResult,
-    music_dir: &'static str,
-    app_data_dir: &'static str,
-    desktop_dir: &'static str,
-    documents_dir: &'static str,
-    downloads_dir: &'static str,
-    pictures_dir: &'static str,
-    videos_dir: &'static str,
-}
-
-enum CommonRootDir {
-    Home(String),
-    Music(String),
-    AppData(String),
-    Desktop(String),
-    Documents(String),
-    Downloads(String),
-    Pictures(String),
-    Videos(String),
-
-    Drive(String),
-}
-
-impl Platform {
-    fn mac() -> Self {
-        Self {
-            prefix: MAC_ICON,
-            sep: POSIX_SEP,
-            parse_sep: Self::parse_posix_sep,
-            home_dir: Self::mac_home_dir,
-            parse_home_dir: Self::parse_mac_home_dir,
-            drive_dir: Self::mac_drive_dir,
-            parse_drive_dir: Self::parse_mac_drive_dir, 
-            app_data_dir: "Library/Application Support",
-            ..Platform::default()
-        }
-    }
-
-    fn linux() -> Self {
-        Self {
-            prefix: LINUX_ICON,
-            sep: POSIX_SEP,
-            parse_sep: Self::parse_posix_sep,
-            home_dir: Self::linux_home_dir,
-            parse_home_dir: Self::parse_linux_home_dir,
-            drive_dir: Self::linux_drive_dir, 
-            parse_drive_dir: Self::parse_linux_drive_dir, 
-            app_data_dir: ".local/share",
-            ..Platform::default()
-        }
-    }
-
-    fn windows() -> Self {
-        Self {
-            prefix: WINDOWS_ICON,
-            sep: WINDOWS_SEP,
-            parse_sep: Self::parse_windows_sep,
-            home_dir: Self::windows_home_dir,
-            parse_home_dir: Self::parse_windows_home_dir,
-            drive_dir: Self::windows_drive_dir, 
-            parse_drive_dir: Self::parse_windows_drive_dir, 
-            app_data_dir: "AppData\\Local",
-            ..Platform::default()
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlatformKind {
+    Mac,
+    Linux,
+    Windows,
+}
 
-    fn default() -> Self {
-        Self {
-            prefix: LINUX_ICON,
-            sep: POSIX_SEP,
-            parse_sep: Self::parse_fail,
-            home_dir: Self::linux_home_dir,
-            parse_home_dir: Self::parse_fail,
-            drive_dir: Self::linux_drive_dir,
-            parse_drive_dir: Self::parse_fail, 
-            music_dir: "Music",
-            app_data_dir: "AppData",
-            desktop_dir: "Desktop",
-            documents_dir: "Documents",
-            downloads_dir: "Downloads",
-            pictures_dir: "Pictures",
-            videos_dir: "Videos",
-        }
-    }
-
-    fn parse_filename_platform(i: &str) -> ParseResult<Self> {
-        alt((
-                map(char(MAC_ICON), |_| Self::mac()),
-                map(char(LINUX_ICON), |_| Self::linux()),
-                map(char(WINDOWS_ICON), |_| Self::windows()),
-        ))(i)
-    }
-
-    fn sniff_path_platform(i: &str) -> ParseResult<Self> {
-        peek(alt((
-                    map(alt((Self::parse_mac_home_dir, Self::parse_mac_drive_dir)), |_| Self::mac()),
-                    map(alt((Self::parse_linux_home_dir, Self::parse_linux_drive_dir)), |_| Self::linux()),
-                    map(alt((Self::parse_windows_home_dir, Self::parse_windows_drive_dir)), |_| Self::windows()),
-        )))(i)
-    }
-
-    fn parse_filename_prefix<'a>(&self, i: &'a str, escaper: &'a Escaper) -> ParseResult<'a, String> {
-        alt((
-                map(preceded(char(HOME_ICON), escaper.unescape_path_comp(self.sep)), |user| (self.home_dir)(&user)),
-                map(preceded(char(MUSIC_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.music_dir)),
-                map(preceded(char(APP_DATA_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.app_data_dir)),
-                map(preceded(char(DESKTOP_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.desktop_dir)),
-                map(preceded(char(DOCUMENTS_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.documents_dir)),
-                map(preceded(char(DOWNLOADS_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.downloads_dir)),
-                map(preceded(char(PICTURES_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.pictures_dir)),
-                map(preceded(char(VIDEOS_ICON), escaper.unescape_path_comp(self.sep)), |user| format!("{}{}{}", (self.home_dir)(&user), self.sep, self.videos_dir)),
-                map(preceded(char(DRIVE_ICON), escaper.unescape_path_comp(self.sep)), |volume| (self.drive_dir)(&volume)),
-        ))(i)
-    }
-
-    fn parse_path_prefix<'a>(&self, i: &'a str, escaper: &'a Escaper) -> (&'a str, String) {
-        use CommonRootDir::*;
-
-        let sep = self.parse_sep;
-
-        let (i, dir) = match (self.parse_home_dir)(i) {
-            Ok((i, user)) => {
-                alt((
-                        map(delimited(sep, Self::tag_or_fail(self.music_dir), peek(alt((sep, eof)))), |_| Music(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.app_data_dir), peek(alt((sep, eof)))), |_| AppData(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.desktop_dir), peek(alt((sep, eof)))), |_| Desktop(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.documents_dir), peek(alt((sep, eof)))), |_| Documents(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.downloads_dir), peek(alt((sep, eof)))), |_| Downloads(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.pictures_dir), peek(alt((sep, eof)))), |_| Pictures(escaper.escape(user))),
-                        map(delimited(sep, Self::tag_or_fail(self.videos_dir), peek(alt((sep, eof)))), |_| Videos(escaper.escape(user))),
-                        map(success(()), |_| Home(escaper.escape(user))),
-                ))(i).expect("using success, it cannot be failed here")
-            },
-            Err(_) => {
-                let (i, volume) = (self.parse_drive_dir)(i).expect("sniffing in advance, it cannot be failed here");
-                (i, Drive(escaper.escape(volume)))
-            },
-        };
+type ParseResult<'a, T = &'a str> = IResult<&'a str, T, nom::error::Error<&'a str>>;
 
-        (i, match dir {
-            Home(user) => format!("{}{}", HOME_ICON, user),
-            Music(user) => format!("{}{}", MUSIC_ICON, user),
-            AppData(user) => format!("{}{}", APP_DATA_ICON, user),
-            Desktop(user) => format!("{}{}", DESKTOP_ICON, user),
-            Documents(user) => format!("{}{}", DOCUMENTS_ICON, user),
-            Downloads(user) => format!("{}{}", DOWNLOADS_ICON, user),
-            Pictures(user) => format!("{}{}", PICTURES_ICON, user),
-            Videos(user) => format!("{}{}", VIDEOS_ICON, user),
-            Drive(volume) => format!("{}{}", DRIVE_ICON, volume),
-        })
-    }
+/// A common root directory which is compressed to an icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommonDir {
+    Home,
+    Music,
+    AppData,
+    Desktop,
+    Documents,
+    Downloads,
+    Pictures,
+    Videos,
+    Caches,
+    Drive,
+}
 
-    fn tag_or_fail<'a>(name: &'a str) -> impl Fn(&'a str) -> ParseResult<'a> {
-        move |i: &'a str| {
-            tag(name)(i)
+impl CommonDir {
+    pub fn icon(self) -> char {
+        match self {
+            CommonDir::Home => HOME_ICON,
+            CommonDir::Music => MUSIC_ICON,
+            CommonDir::AppData => APP_DATA_ICON,
+            CommonDir::Desktop => DESKTOP_ICON,
+            CommonDir::Documents => DOCUMENTS_ICON,
+            CommonDir::Downloads => DOWNLOADS_ICON,
+            CommonDir::Pictures => PICTURES_ICON,
+            CommonDir::Videos => VIDEOS_ICON,
+            CommonDir::Caches => CACHES_ICON,
+            CommonDir::Drive => DRIVE_ICON,
         }
     }
 
-    fn mac_home_dir(user: &str) -> String {
-        "/Users/".to_string() + user
-    }
-
-    fn linux_home_dir(user: &str) -> String {
-        "/home/".to_string() + user
-    }
-
-    fn windows_home_dir(user: &str) -> String {
-        "C:\\Users\\".to_string() + user
-    }
-
-    fn parse_mac_home_dir(i: &str) -> ParseResult {
-        delimited(tag("/Users/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
-    }
-
-    fn parse_linux_home_dir(i: &str) -> ParseResult {
-        delimited(tag("/home/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
-    }
-
-    fn parse_windows_home_dir(i: &str) -> ParseResult {
-        delimited(tag("C:\\Users\\"), Self::parse_windows_path_comp, peek(alt((Self::parse_windows_sep, eof))))(i)
-    }
-
-    fn mac_drive_dir(volume: &str) -> String {
-        "/Volumes/".to_string() + volume
-    }
-
-    fn linux_drive_dir(volume: &str) -> String {
-        "/media/".to_string() + volume
-    }
-
-    fn windows_drive_dir(volume: &str) -> String {
-        volume.to_string() + ":"
-    }
-
-    fn parse_mac_drive_dir(i: &str) -> ParseResult {
-        delimited(tag("/Volumes/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
-    }
-
-    fn parse_linux_drive_dir(i: &str) -> ParseResult {
-        delimited(tag("/media/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
-    }
-
-    fn parse_windows_drive_dir(i: &str) -> ParseResult {
-        terminated(recognize(satisfy(|c| c.is_alphabetic())), char(':'))(i)
-    }
-
-    fn parse_posix_sep(i: &str) -> ParseResult {
-        recognize(char(POSIX_SEP))(i)
-    }
-
-    fn parse_posix_path_comp(i: &str) -> ParseResult {
-        take_while1(|c| c != POSIX_SEP)(i)
-    }
-
-    fn parse_windows_sep(i: &str) -> ParseResult {
-        recognize(char(WINDOWS_SEP))(i)
+    pub fn from_icon(icon: char) -> Option<Self> {
+        [
+            CommonDir::Home, CommonDir::Music, CommonDir::AppData, CommonDir::Desktop, CommonDir::Documents,
+            CommonDir::Downloads, CommonDir::Pictures, CommonDir::Videos, CommonDir::Caches, CommonDir::Drive,
+        ].into_iter().find(|dir| dir.icon() == icon)
     }
+}
 
-    fn parse_windows_path_comp(i: &str) -> ParseResult {
-        take_while1(|c| c != WINDOWS_SEP)(i)
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RegisteredDir {
+    icon: char,
+    name: String,
+}
 
-    fn parse_fail(i: &str) -> ParseResult {
-        fail(i)
-    }
+// a common dir the local machine keeps somewhere else than the usual place, like Documents moved to `D:\Docs`
+#[cfg(feature = "platform")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Relocation {
+    canonical: String,
+    actual: String,
+    // whether it's a mount point, which isn't relocated in a path that has a common root dir
+    mount: bool,
 }
 
+#[derive(Debug, Clone)]
 struct Escaper {
     escaping_map: HashMap<char, String>,
     unescaping_map: HashMap<String, char>,
+    compact_escapes: bool,
+    transliterator: Option<Arc<dyn Transliterator>>,
 }
 
 impl Escaper {
-    fn new() -> Self {
+    fn new(substitutes: &[(char, char)], doubled: &[char], compact_escapes: bool, transliterator: Option<Arc<dyn Transliterator>>) -> Self {
         let mut escaping_map = HashMap::new();
         let mut unescaping_map = HashMap::new();
-        for (target, escaped) in zip(ESCAPE_TARGET_CHARS.chars(), ESCAPED_CHARS.chars()) {
+        let targets = ESCAPE_TARGET_CHARS.chars().chain(substitutes.iter().map(|(target, _)| *target));
+        let escaped_chars = ESCAPED_CHARS.chars().chain(substitutes.iter().map(|(_, escaped)| *escaped));
+        for (target, escaped) in zip(targets, escaped_chars.clone()) {
             escaping_map.insert(target, escaped.to_string());
             unescaping_map.insert(escaped.to_string(), target);
         }
-        for c in ESCAPED_CHARS.chars() {
+        for c in escaped_chars.chain(doubled.iter().copied()).chain([COMPACT_ESCAPE_OPEN]) {
             let mut escaped_str = c.to_string();
             escaped_str.push(c);
             escaping_map.insert(c, escaped_str.clone());
@@ -379,22 +413,72 @@ impl Escaper {
         Self {
             escaping_map,
             unescaping_map,
+            compact_escapes,
+            transliterator,
         }
     }
 
+    // whether `s` has none of the chars escaping or unescaping changes, so it's encoded and decoded as is
+    fn is_plain(&self, s: &str) -> bool {
+        !s.chars().any(|c| self.escaping_map.contains_key(&c))
+    }
+
     fn escape(&self, s: &str) -> String {
         let mut r = String::new();
-        for c in s.chars() {
-            if let Some(escaped) = self.escaping_map.get(&c) {
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let Some(escaped) = self.escaping_map.get(&c) else {
+                match &self.transliterator {
+                    Some(transliterator) if !transliterator.is_allowed(c) => self.push_transliteration(transliterator.as_ref(), c, &mut chars, &mut r),
+                    _ => r.push(c),
+                }
+                continue;
+            };
+            let mut n = 1;
+            if self.compact_escapes && escaped.chars().all(|e| e == c) {
+                while n < u16::MAX && chars.next_if_eq(&c).is_some() {
+                    n += 1;
+                }
+                let compact = Self::compact_escape(c, n);
+                if compact.len() < escaped.len() * n as usize {
+                    r.push_str(&compact);
+                    continue;
+                }
+            }
+            for _ in 0..n {
                 r.push_str(escaped);
-            } else {
-                r.push(c);
             }
         };
         r
     }
 
+    // `〔4F〕` for a char, or `〔4F*12〕` for a run of it
+    fn compact_escape(c: char, n: u16) -> String {
+        match n {
+            1 => format!("{}{:X}{}", COMPACT_ESCAPE_OPEN, c as u32, COMPACT_ESCAPE_CLOSE),
+            n => format!("{}{:X}*{}{}", COMPACT_ESCAPE_OPEN, c as u32, n, COMPACT_ESCAPE_CLOSE),
+        }
+    }
+
+    fn parse_compact_escape(i: &str) -> ParseResult<'_, (char, u16)> {
+        delimited(
+            char(COMPACT_ESCAPE_OPEN),
+            pair(
+                map_opt(hex_digit1, |hex| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)),
+                map(opt(preceded(char('*'), verify(map_res(digit1, str::parse), |n: &u16| *n > 0))), |n| n.unwrap_or(1)),
+            ),
+            char(COMPACT_ESCAPE_CLOSE),
+        )(i)
+    }
+
     fn unescape_char<'a>(&'a self, i: &'a str) -> ParseResult<'a, String> {
+        let (i, _) = not(alt((recognize(Self::parse_counter), recognize(Self::parse_trailing_sep))))(i)?;
+        if let Ok((i, (c, n))) = Self::parse_compact_escape(i) {
+            return Ok((i, c.to_string().repeat(n as usize)));
+        }
+        if let Ok((i, original)) = Self::parse_transliteration(i) {
+            return Ok((i, original));
+        }
         map(alt((verify(take(2usize), |s: &str| self.unescaping_map.contains_key(s)), take(1usize))), |s: &str| {
             if let Some(c) = self.unescaping_map.get(s) {
                 String::from(*c)
@@ -415,6 +499,42 @@ impl Escaper {
         )(i)
     }
 
+    fn parse_counter(i: &str) -> ParseResult<'_, u32> {
+        terminated(preceded(char(COUNTER_ICON), map_res(digit1, str::parse)), eof)(i)
+    }
+
+    fn parse_trailing_sep(i: &str) -> ParseResult<'_, char> {
+        terminated(char(TRAILING_SEP_ICON), peek(alt((eof, recognize(Self::parse_counter)))))(i)
+    }
+
+    // counts the chars the compact escapes in `i` expand to, walking it the way `unescape` does
+    fn compact_expansion(&self, mut i: &str) -> usize {
+        let mut expansion = 0usize;
+        while !i.is_empty() {
+            if let Ok((rest, (_, n))) = Self::parse_compact_escape(i) {
+                expansion = expansion.saturating_add(n as usize);
+                i = rest;
+                continue;
+            }
+            if let Ok((rest, original)) = Self::parse_transliteration(i) {
+                expansion = expansion.saturating_add(original.chars().count());
+                i = rest;
+                continue;
+            }
+            let doubled: ParseResult<'_> = verify(take(2usize), |s: &str| self.unescaping_map.contains_key(s))(i);
+            i = match doubled {
+                Ok((rest, _)) => rest,
+                Err(_) => {
+                    let mut chars = i.chars();
+                    chars.next();
+                    chars.as_str()
+                },
+            };
+        }
+        expansion
+    }
+
+    #[cfg(feature = "platform")]
     fn unescape_path_comp<'a>(&'a self, sep: char) -> impl FnMut(&'a str) -> ParseResult<'a, String> {
         move |i| {
             fold_many0(
@@ -429,150 +549,1899 @@ impl Escaper {
     }
 }
 
-pub fn to_path(filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
-    let filename = filename.as_ref();
-    let Some(filename) = filename.to_str() else {
-        return Err(Error::CouldntEncodeToUtf8(filename.into()));
-    };
-    to_path_from_str(filename)
+/// The `(name, original_path)` pairs and the `(name, error)` pairs [`Codec::decode_batch`] returns.
+pub type DecodedBatch = (Vec<(String, PathBuf)>, Vec<(String, Error)>);
+
+/// How ASCII spaces are written in encoded filenames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SpaceReplacement {
+    /// Spaces are kept as is.
+    #[default]
+    Keep,
+    /// Spaces are replaced to `␣` (U+2423).
+    OpenBox,
+    /// Spaces are replaced to a no-break space (U+00A0).
+    NoBreakSpace,
+    /// Spaces are replaced to a full width space (U+3000).
+    IdeographicSpace,
 }
 
-pub fn to_path_from_str(filename: impl AsRef<str>) -> Result<PathBuf, Error> {
-    let escaper = Escaper::new();
+impl SpaceReplacement {
+    fn substitute(self) -> Option<char> {
+        match self {
+            SpaceReplacement::Keep => None,
+            SpaceReplacement::OpenBox => Some('␣'),
+            SpaceReplacement::NoBreakSpace => Some('\u{a0}'),
+            SpaceReplacement::IdeographicSpace => Some('\u{3000}'),
+        }
+    }
+}
 
-    let i = filename.as_ref();
-    let (i, prefix) = match Platform::parse_filename_platform(i) {
-        Ok((i, platform)) => {
-            let (i, prefix) = platform.parse_filename_prefix(i, &escaper)?;
-            (i, prefix)
-        },
-        Err(_) => (i, "".to_string()),
-    };
-    let (i, path) = escaper.unescape(i).expect("it shouldn't be an error if the escaper design is correct");
+/// The unit a filesystem counts filename lengths in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FsProfile {
+    /// UTF-8 bytes, as ext4, btrfs, APFS and most POSIX filesystems count.
+    #[default]
+    Utf8,
+    /// UTF-16 code units, as NTFS, exFAT and FAT long names count.
+    Ntfs,
+    /// UTF-8 bytes of the NFD form, as HFS+ stores filenames decomposed.
+    HfsPlus,
+}
 
-    assert_eq!(i.len(), 0);
+impl FsProfile {
+    /// Returns the length of `filename` in the unit of this filesystem.
+    pub fn filename_len(self, filename: &str) -> usize {
+        match self {
+            FsProfile::Utf8 => filename.len(),
+            FsProfile::Ntfs => filename.encode_utf16().count(),
+            FsProfile::HfsPlus => filename.nfd().map(char::len_utf8).sum(),
+        }
+    }
+}
 
-    Ok(PathBuf::from(prefix + &path))
+/// How a trailing separator of a path is encoded.
+///
+/// The trailing separator here is the one of the detected platform, or `/` for paths of no platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TrailingSeparator {
+    /// Trailing separators are escaped as any other separator, e.g. `/home/alice/Desktop/` to `🐧🔝alice／`.
+    #[default]
+    Preserve,
+    /// Trailing separators are dropped, so `/home/alice/Desktop/` and `/home/alice/Desktop` encode to the same
+    /// filename. This isn't reversible.
+    Strip,
+    /// A trailing separator is encoded to `📂`, e.g. `/home/alice/Desktop/` to `🐧🔝alice📂`.
+    Mark,
 }
 
-pub fn to_filename(path: impl AsRef<Path>) -> Result<String, Error> {
-    let path = path.as_ref();
-    let path = path.as_os_str();
-    let Some(path) = path.to_str() else {
-        return Err(Error::CouldntEncodeToUtf8(path.into()));
-    };
-    Ok(to_filename_from_str(path))
+/// How the drive letter of a windows path is encoded.
+#[cfg(feature = "platform")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DriveLetterCase {
+    /// The drive letter is kept as is, so `c:\x` and `C:\x` encode to different filenames.
+    #[default]
+    Preserve,
+    /// The drive letter is uppercased, and `🔡` after the platform icon records it was lowercase, e.g. `c:\x`
+    /// to `💠🔡🥞C＼x`. The lowercase `/users` of macOS is recorded the same way whatever this is.
+    Uppercase,
 }
 
-pub fn to_filename_from_str(path: impl AsRef<str>) -> String {
-    let escaper = Escaper::new();
+/// How decoding restores a root whose original lowercase spelling is recorded in the filename.
+#[cfg(feature = "platform")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CaseRestoration {
+    /// The original spelling is restored, e.g. `💠🔡🥞C＼x` to `c:\x`.
+    #[default]
+    Original,
+    /// The canonical spelling is returned, e.g. `💠🔡🥞C＼x` to `C:\x`.
+    Canonical,
+}
 
-    let i = path.as_ref();
-    let (i, platform) = match Platform::sniff_path_platform(i) {
-        Ok((i, platform)) => (i, Some(platform)),
-        Err(_) => (i, None),
-    };
+/// What decoding does with the icon-like chars a filename starts with where this codec doesn't know them, like
+/// the icon of a platform or a registered dir of another version or configuration.
+///
+/// The chars of the emoji and symbol blocks look like icons, and the icons and escaped chars of the codec, like
+/// `🍏` or `⏩`, are known ones. Since the encoder keeps such chars of relative paths as they are, `Error` and
+/// `Strip` are only for listings that never have relative paths.
+#[cfg(feature = "platform")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnknownIconPolicy {
+    /// They are decoded as literal chars, e.g. `🦀／tmp` to `🦀/tmp`.
+    #[default]
+    PassThrough,
+    /// Decoding fails with [`Error::ParseError`].
+    Error,
+    /// They are dropped and the rest is decoded, e.g. `🦀／tmp` to `/tmp`.
+    Strip,
+}
 
-    let (i, prefix) = if let Some(platform) = platform {
-        let mut prefix = String::new();
-        prefix.push(platform.prefix);
+/// Builds a [`Codec`] with non-default settings.
+///
+/// Filenames encoded by a codec must be decoded by a codec built with the same settings.
+#[derive(Debug, Clone, Default)]
+pub struct CodecBuilder {
+    spaces: SpaceReplacement,
+    trailing_separator: TrailingSeparator,
+    collapse_separators: bool,
+    link_notation: bool,
+    compact_escapes: bool,
+    transliterator: Option<Arc<dyn Transliterator>>,
+    header: bool,
+    escaping_only: bool,
+    #[cfg(feature = "platform")]
+    drive_letter_case: DriveLetterCase,
+    #[cfg(feature = "platform")]
+    case_restoration: CaseRestoration,
+    #[cfg(feature = "platform")]
+    unknown_icons: UnknownIconPolicy,
+    #[cfg(feature = "platform")]
+    relocations: Vec<Relocation>,
+    #[cfg(feature = "platform")]
+    uncompressed: Vec<CommonDir>,
+    dirs: Vec<RegisteredDir>,
+    decode_cache: Option<usize>,
+    max_input_len: Option<usize>,
+    max_escape_expansion: Option<usize>,
+    #[cfg(feature = "platform")]
+    intern_tokens: Option<usize>,
+    #[cfg(all(windows, feature = "short-names"))]
+    expand_short_names: bool,
+    collect_stats: bool,
+}
 
-        let (i, p) = platform.parse_path_prefix(i, &escaper);
-        prefix.push_str(&p);
-        (i, prefix)
-    } else {
-        (i, String::new())
-    };
+impl CodecBuilder {
+    pub fn spaces(mut self, spaces: SpaceReplacement) -> Self {
+        self.spaces = spaces;
+        self
+    }
 
-    prefix + &escaper.escape(i)
-}
+    pub fn trailing_separator(mut self, trailing_separator: TrailingSeparator) -> Self {
+        self.trailing_separator = trailing_separator;
+        self
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use ucd::Codepoint;
-    use ucd::tables::misc::EastAsianWidth::*;
-    use nom::{
-        Needed,
-        error::ErrorKind,
-    };
+    /// Collapses runs of separators, like `/home//alice///file` to `/home/alice/file`, before encoding.
+    ///
+    /// It's off by default, which keeps the encoding byte-exact. Turned on, paths differing only in repeated
+    /// separators share a canonical filename, and decoding returns the collapsed path, not the original bytes.
+    /// The separator is the one of the detected platform, or `/` for paths of no platform, and a leading run
+    /// is kept as is since it's meaningful in UNC paths like `\\\\server\\share`.
+    pub fn collapse_separators(mut self, collapse_separators: bool) -> Self {
+        self.collapse_separators = collapse_separators;
+        self
+    }
 
-    #[test]
-    fn check_chars() {
-        assert_eq!(ESCAPE_TARGET_CHARS.chars().collect::<Vec<_>>().len(), ESCAPED_CHARS.chars().collect::<Vec<_>>().len());
-        for c in ESCAPED_CHARS.chars() {
-            assert_explicit_width(c);
+    /// Encodes the `path -> target` notation `ls -l` prints for links, encoding both sides and joining them with `🔀`.
+    ///
+    /// It's off by default, when ` -> ` is escaped like any other chars. Turned on, `/home/alice/link -> /tmp/x` is
+    /// encoded to `🐧🏠alice／link🔀／tmp／x`, and decoding gets the notation back.
+    pub fn link_notation(mut self, link_notation: bool) -> Self {
+        self.link_notation = link_notation;
+        self
+    }
+
+    /// Writes a run of chars escaped by doubling, like `／／／／／`, as a code point and a count, like `〔FF0F*5〕`,
+    /// where it's shorter.
+    ///
+    /// It's off by default. Either way, the decoder accepts both forms, and a literal `〔` is doubled, so the
+    /// filenames of codecs with and without it decode alike. That changes the 0.1 filenames holding `〔`.
+    pub fn compact_escapes(mut self, compact_escapes: bool) -> Self {
+        self.compact_escapes = compact_escapes;
+        self
+    }
+
+    /// Writes the chars outside the repertoire of `transliterator` as compact escapes holding their spelling in
+    /// it, like `〔Privet=41F,440,438,432,435,442〕` for `Привет` with [`CyrillicToLatin`].
+    ///
+    /// It's off by default. Only the chars the codec keeps as is are transliterated, not its own icons and
+    /// escapes, and chars of no spelling are written as their codes, like `〔65E5〕`. So the filenames still hold
+    /// `〔〕` and the fullwidth substitutes of escaped chars, like `／`, outside the repertoire. Any codec decodes
+    /// them, but the name of the transliterator is a part of the config string, like `translit=cyrillic-latin`,
+    /// since the encoding differs, and [`Codec::from_config_string`] only knows [`CyrillicToLatin`].
+    pub fn transliterate(mut self, transliterator: impl Transliterator + 'static) -> Self {
+        self.transliterator = Some(Arc::new(transliterator));
+        self
+    }
+
+    /// Starts every filename with a header of the settings differing from the defaults, like
+    /// `🧾v1;spaces=open-box🧾／tmp／my␣file`, so [`decode_auto`] decodes it without knowing the settings.
+    ///
+    /// It's off by default. The codec decodes filenames with its own header and ones without any, like the
+    /// names of [`Codec::encode_basename`], and fails on the ones with another header. With it, a literal `🧾`
+    /// is escaped to `📜` and a literal `📜` is doubled, while codecs without it keep both as they are.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Escapes paths char by char without compressing their OS and common dirs to icons, and decodes the icons a
+    /// filename starts with as literal chars, like builds without the `platform` feature do.
+    ///
+    /// It's off by default with the feature, and always on without it. So a codec with it encodes and decodes
+    /// alike in both builds, and its config string, holding `escaping-only=true`, reads the same in both, while
+    /// the filenames of the default codec of one build don't decode the same in the other.
+    pub fn escaping_only(mut self, escaping_only: bool) -> Self {
+        self.escaping_only = escaping_only;
+        self
+    }
+
+    #[cfg(feature = "platform")]
+    pub fn drive_letter_case(mut self, drive_letter_case: DriveLetterCase) -> Self {
+        self.drive_letter_case = drive_letter_case;
+        self
+    }
+
+    #[cfg(feature = "platform")]
+    pub fn case_restoration(mut self, case_restoration: CaseRestoration) -> Self {
+        self.case_restoration = case_restoration;
+        self
+    }
+
+    #[cfg(feature = "platform")]
+    pub fn unknown_icons(mut self, unknown_icons: UnknownIconPolicy) -> Self {
+        self.unknown_icons = unknown_icons;
+        self
+    }
+
+    /// Turns the compression of a common directory `dir` off or back on, keeping the others.
+    ///
+    /// All of them are compressed by default. A path in a sub dir turned off is compressed as its home, like
+    /// `/Users/alice/Downloads/x` to `🍎🏠alice／Downloads／x`, and a path in an uncompressed home or drive is only
+    /// escaped, like `/Volumes/USB/x` to `／Volumes／USB／x`. Filenames with any of the icons still decode.
+    #[cfg(feature = "platform")]
+    pub fn compress_dir(mut self, dir: CommonDir, compress: bool) -> Self {
+        self.uncompressed.retain(|&other| other != dir);
+        if !compress {
+            self.uncompressed.push(dir);
         }
+        self
+    }
 
-        assert_explicit_width(MAC_ICON);
-        assert_explicit_width(LINUX_ICON);
-        assert_explicit_width(WINDOWS_ICON);
+    /// Registers a directory `name` to be compressed to `icon` when it follows a common directory.
+    ///
+    /// Registered directories chain, so with `Projects` registered `/Users/alice/Documents/Projects` is
+    /// encoded to `🍎📄` + icon + `alice`, and a further registered directory stacks another icon. The `icon`
+    /// is escaped by doubling wherever it appears literally. It must be a char of the emoji and symbol blocks
+    /// U+2300–23FF, U+2600–27BF, U+2B00–2BFF and U+1F000–1FAFF the codec doesn't use, or building fails.
+    pub fn register_dir(mut self, icon: char, name: impl Into<String>) -> Self {
+        self.dirs.push(RegisteredDir { icon, name: name.into() });
+        self
+    }
 
-        assert_explicit_width(HOME_ICON);
-        assert_explicit_width(MUSIC_ICON);
-        assert_explicit_width(APP_DATA_ICON);
-        assert_explicit_width(DESKTOP_ICON);
-        assert_explicit_width(DOCUMENTS_ICON);
-        assert_explicit_width(DOWNLOADS_ICON);
-        assert_explicit_width(PICTURES_ICON);
-        assert_explicit_width(VIDEOS_ICON);
-        assert_explicit_width(DRIVE_ICON);
+    /// Compresses and decodes the known folders of the current Windows user at their actual locations, as
+    /// `SHGetKnownFolderPath` reports them.
+    ///
+    /// With Documents relocated to `D:\\Docs`, `D:\\Docs\\x` is encoded to `💠📄alice＼x` and decoded back to
+    /// `D:\\Docs\\x`, so the filenames are only meant to be decoded on the same machine. Resolving fails only if
+    /// the user profile can't be found. On other OSes, it does nothing.
+    ///
+    /// The relocations depend on the machine, so a codec with them can't have a [`CodecBuilder::header`] nor a
+    /// [`PathIndex`], and its config string only names them, see [`Codec::to_config_string`].
+    #[cfg(all(windows, feature = "known-folders"))]
+    pub fn local_known_folders(mut self) -> Result<Self, Error> {
+        self.relocations.extend(known_folders::relocations()?);
+        Ok(self)
     }
 
-    fn assert_explicit_width(c: char) {
-        let w = c.east_asian_width();
-        assert!(w == Narrow || w == Wide || w == HalfWidth || w == FullWidth);
+    /// Does nothing, since there are no known folders outside Windows.
+    #[cfg(all(not(windows), feature = "known-folders"))]
+    pub fn local_known_folders(self) -> Result<Self, Error> {
+        Ok(self)
     }
 
-    #[test]
-    fn it_works() {
-        let pairs = [
-            ("/", "／"),
-            ("🍎", "🍏"),
-            ("/tmp", "／tmp"),
-            ("/media/disk001/file.txt", "🐧🥞disk001／file.txt"),
-            ("C:\\file.txt", "💠🥞C＼file.txt"),
-            ("C:\\Users\\alice\\file.txt", "💠🏠alice＼file.txt"),
-            ("C:\\Users\\alice\\Music\\file.mp3", "💠🎵alice＼file.mp3"),
-            ("/Users/alice/Library/Application Support", "🍎💾alice"),
-            ("/home/alice/Desktop/", "🐧🔝alice／"),
-            ("/home/alice/Documents/file.doc", "🐧📄alice／file.doc"),
-            ("/Users/alice/Downloads/file.txt", "🍎⏬alice／file.txt"),
-            ("C:\\Users\\alice\\Pictures\\file.jpg", "💠🎨alice＼file.jpg"),
-            ("/home/alice/Videos/file.mp4", "🐧🎥alice／file.mp4"),
-            ("/Volumes/disk001/file.txt", "🍎🥞disk001／file.txt"),
-            ("platform_icon_🍎_test", "platform_icon_🍏_test"),
-            ("platform_icon_🐧_test", "platform_icon_🐤_test"),
-            ("platform_icon_💠_test", "platform_icon_🚪_test"),
-            ("all_escape_targets_\0\\/:*?\"<>|🍎🐧💠_test", "all_escape_targets_〇＼／：＊？＂＜＞｜🍏🐤🚪_test"),
-            ("all_escape_escaped_chars_〇＼／：＊？＂＜＞｜🍏🐤🚪_test", "all_escape_escaped_chars_〇〇＼＼／／：：＊＊？？＂＂＜＜＞＞｜｜🍏🍏🐤🐤🚪🚪_test"),
-            ("/Volumes/disk🍎001/file.txt", "🍎🥞disk🍏001／file.txt"),
-            ("/Volumes/disk🐤001/file.txt", "🍎🥞disk🐤🐤001／file.txt"),
-        ];
+    /// Expands 8.3 short name segments, like `C:\\PROGRA~1`, into their long names with `GetLongPathNameW`
+    /// before encoding paths, so `C:\\PROGRA~1\\x` and `C:\\Program Files\\x` make the same filename.
+    ///
+    /// Only the existing part of a path can be resolved, and the rest is kept as it is. Paths without segments
+    /// [`is_short_name`] matches are encoded without touching the filesystem. It only applies to
+    /// [`Codec::to_filename`] and the functions taking a `Path`, not to [`Codec::to_filename_from_str`]. On other
+    /// OSes, it does nothing.
+    ///
+    /// Like the relocations of [`CodecBuilder::mount_table`], it depends on the machine, so a codec with it can't
+    /// have a [`CodecBuilder::header`] nor a [`PathIndex`].
+    #[cfg(all(windows, feature = "short-names"))]
+    pub fn expand_short_names(mut self, expand_short_names: bool) -> Self {
+        self.expand_short_names = expand_short_names;
+        self
+    }
 
-        for (path, filename) in pairs {
-            {
-                let filename = OsString::from(filename);
-                assert_eq!(to_path(filename).unwrap(), PathBuf::from(path));
+    /// Does nothing, since only Windows has short names to resolve.
+    #[cfg(all(not(windows), feature = "short-names"))]
+    pub fn expand_short_names(self, _expand_short_names: bool) -> Self {
+        self
+    }
+
+    /// Compresses the network shares and bind mounts of `table`, a mount table in the format of
+    /// `/proc/self/mountinfo` or of `/proc/mounts`, like the dirs under `/media`, naming them after what's mounted.
+    ///
+    /// With `nas:/export` mounted on `/mnt/music` over NFS, `/mnt/music/x` is encoded to
+    /// `🐧🥞〇nas：%2Fexport／x`, where `/` and `%` of the source are percent-escaped, and decoded back to
+    /// `/mnt/music/x` by a codec with a table mounting the same source anywhere. Without one, it's decoded to
+    /// `/media/\0nas:%2Fexport/x`, whose NUL keeps it apart from a volume named `nas:%2Fexport` under `/media`.
+    /// A bind mount is named after its device and the dir of the filesystem it mounts, like
+    /// `/dev/sda1:/data`, and is only told apart from other mounts in the format of `/proc/self/mountinfo`,
+    /// where a btrfs subvolume isn't taken for one. Paths in a common root dir, like a home, are encoded as usual
+    /// even when something is mounted in it, and so are the other mounts, like the ones of devices under `/media`.
+    ///
+    /// The mounts depend on the machine, so a codec relocating any can't have a [`CodecBuilder::header`] nor a
+    /// [`PathIndex`], and its config string only names them, like `local=mounts`.
+    #[cfg(feature = "mounts")]
+    pub fn mount_table(mut self, table: impl AsRef<str>) -> Self {
+        self.relocations.extend(mounts::relocations(table.as_ref()));
+        self
+    }
+
+    /// Compresses the network shares and bind mounts of this machine, as `/proc/self/mountinfo` lists them.
+    ///
+    /// See [`CodecBuilder::mount_table`]. Reading fails only if `/proc` isn't mounted.
+    #[cfg(all(target_os = "linux", feature = "mounts"))]
+    pub fn local_mounts(self) -> Result<Self, Error> {
+        Ok(self.mount_table(fs::read_to_string("/proc/self/mountinfo")?))
+    }
+
+    /// Caches up to `capacity` decoded filenames, so decoding the same ones again, like on every refresh of a
+    /// directory listing, skips the parsers.
+    pub fn decode_cache(mut self, capacity: usize) -> Self {
+        self.decode_cache = Some(capacity);
+        self
+    }
+
+    /// Rejects filenames longer than `len` UTF-8 bytes with [`Error::LimitExceeded`] before decoding them.
+    ///
+    /// It's unlimited by default. Along with [`CodecBuilder::max_escape_expansion`], it bounds the CPU and memory
+    /// it takes to decode an untrusted filename. The limits don't change what decodes within them, so they
+    /// aren't part of [`Codec::to_config_string`].
+    pub fn max_input_len(mut self, len: usize) -> Self {
+        self.max_input_len = Some(len);
+        self
+    }
+
+    /// Rejects filenames whose compact escapes, like `〔41*65535〕`, expand to more than `chars` chars in total
+    /// with [`Error::LimitExceeded`] before decoding them.
+    ///
+    /// It's unlimited by default, where a single compact escape of a few bytes decodes to tens of thousands of
+    /// chars.
+    pub fn max_escape_expansion(mut self, chars: usize) -> Self {
+        self.max_escape_expansion = Some(chars);
+        self
+    }
+
+    /// Interns up to `capacity` escaped usernames and volumes, so encoding many paths of a few users or volumes
+    /// escapes each of them once. The clones of the built codec share the interner across threads, and
+    /// [`Codec::intern_stats`] tells how well it works.
+    #[cfg(feature = "platform")]
+    pub fn intern_tokens(mut self, capacity: usize) -> Self {
+        self.intern_tokens = Some(capacity);
+        self
+    }
+
+    /// Counts the paths the codec encodes, with the chars escaped, the prefixes compressed and the bytes in and
+    /// out, for [`Codec::stats`]. The clones of the built codec share the counts across threads.
+    ///
+    /// It's off by default, since counting the escapes reads every filename again.
+    pub fn collect_stats(mut self, collect_stats: bool) -> Self {
+        self.collect_stats = collect_stats;
+        self
+    }
+
+    pub fn build(self) -> Result<Codec, Error> {
+        let mut reserved: Vec<char> = ESCAPE_TARGET_CHARS.chars().chain(ESCAPED_CHARS.chars()).chain(RESERVED_ICONS).collect();
+        reserved.push(' ');
+        reserved.extend([COMPACT_ESCAPE_OPEN, HEADER_ICON, HEADER_ESCAPED_ICON]);
+        reserved.extend(self.spaces.substitute());
+
+        for (n, dir) in self.dirs.iter().enumerate() {
+            if !is_icon_like(dir.icon) {
+                return Err(Error::InvalidConfig(format!("icon {:?} of the registered dir {:?} isn't an emoji or symbol", dir.icon, dir.name)));
             }
-            {
-                let path = PathBuf::from(path);
-                assert_eq!(to_filename(path).unwrap(), filename);
+            if reserved.contains(&dir.icon) || self.dirs.iter().take(n).any(|other| other.icon == dir.icon) {
+                return Err(Error::InvalidConfig(format!("icon {} of the registered dir {:?} is already in use", dir.icon, dir.name)));
             }
+            if dir.name.is_empty() || dir.name.contains([POSIX_SEP, WINDOWS_SEP]) {
+                return Err(Error::InvalidConfig(format!("registered dir {:?} must be a single path component", dir.name)));
+            }
+        }
+        let codec = self.build_valid();
+        let local = codec.local_settings();
+        if codec.header.is_some() && !local.is_empty() {
+            return Err(Error::InvalidConfig(format!("a header can't hold the {} of this machine", local.join(" and "))));
         }
+        Ok(codec)
     }
 
-    #[test]
-    fn parse_error() {
-        assert_eq!(to_path("🍎invalid"), Err(Error::ParseError(nom::error::Error { input: "invalid".into(), code: ErrorKind::Char })));
+    fn build_valid(self) -> Codec {
+        let mut substitutes: Vec<_> = self.spaces.substitute().map(|c| (' ', c)).into_iter().collect();
+        if self.header {
+            substitutes.push((HEADER_ICON, HEADER_ESCAPED_ICON));
+        }
+        let icons: Vec<_> = self.dirs.iter().map(|dir| dir.icon).collect();
+        let header = self.header;
+        let mut codec = Codec {
+            escaper: Arc::new(Escaper::new(&substitutes, &icons, self.compact_escapes, self.transliterator)),
+            spaces: self.spaces,
+            trailing_separator: self.trailing_separator,
+            collapse_separators: self.collapse_separators,
+            link_notation: self.link_notation,
+            header: None,
+            escaping_only: self.escaping_only || cfg!(not(feature = "platform")),
+            #[cfg(feature = "platform")]
+            drive_letter_case: self.drive_letter_case,
+            #[cfg(feature = "platform")]
+            case_restoration: self.case_restoration,
+            #[cfg(feature = "platform")]
+            unknown_icons: self.unknown_icons,
+            #[cfg(feature = "platform")]
+            relocations: self.relocations.into(),
+            #[cfg(feature = "platform")]
+            uncompressed: self.uncompressed.into(),
+            dirs: self.dirs.into(),
+            decode_cache: self.decode_cache.map(|capacity| Arc::new(Mutex::new(DecodeCache::new(capacity)))),
+            max_input_len: self.max_input_len,
+            max_escape_expansion: self.max_escape_expansion,
+            #[cfg(feature = "platform")]
+            interner: self.intern_tokens.map(|capacity| Arc::new(Interner::new(capacity))),
+            #[cfg(all(windows, feature = "short-names"))]
+            expand_short_names: self.expand_short_names,
+            stats: self.collect_stats.then(|| Arc::new(StatsCollector::default())),
+        };
+        if header {
+            codec.header = Some(codec.header_string().into());
+        }
+        codec
     }
+}
 
-    #[test]
-    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
-    fn parse_error_in_unix() {
-        use std::os::unix::ffi::OsStringExt;
-        assert_eq!(to_path(OsString::from_vec(vec![0xc3u8, 0x28u8])), Err(Error::CouldntEncodeToUtf8(OsString::from_vec(vec![0xc3u8, 0x28u8]))));
-        assert_eq!(to_filename(PathBuf::from(OsString::from_vec(vec![0xc3u8, 0x28u8]))), Err(Error::CouldntEncodeToUtf8(OsString::from_vec(vec![0xc3u8, 0x28u8]))));
+/// Encodes paths to filenames and decodes them back with a fixed set of settings.
+///
+/// The free functions of this crate behave like a default `Codec`.
+///
+/// ```rust
+/// use path_to_unicode_filename::*;
+///
+/// let codec = Codec::builder().spaces(SpaceReplacement::OpenBox).build().unwrap();
+/// assert_eq!(codec.to_filename("/tmp/my file.txt"), Ok("／tmp／my␣file.txt".into()));
+/// assert_eq!(codec.to_path("／tmp／my␣file.txt"), Ok("/tmp/my file.txt".into()));
+/// ```
+///
+/// A `Codec` is `Send + Sync`, and cloning it only bumps reference counts, so one configured instance can be
+/// shared across threads, like in the state of a web service.
+#[derive(Debug, Clone)]
+pub struct Codec {
+    escaper: Arc<Escaper>,
+    spaces: SpaceReplacement,
+    trailing_separator: TrailingSeparator,
+    collapse_separators: bool,
+    link_notation: bool,
+    // the header every filename starts with, if enabled
+    header: Option<Arc<str>>,
+    // whether OS and common dirs are left uncompressed, always without the platform feature
+    escaping_only: bool,
+    #[cfg(feature = "platform")]
+    drive_letter_case: DriveLetterCase,
+    #[cfg(feature = "platform")]
+    case_restoration: CaseRestoration,
+    #[cfg(feature = "platform")]
+    unknown_icons: UnknownIconPolicy,
+    #[cfg(feature = "platform")]
+    relocations: Arc<[Relocation]>,
+    #[cfg(feature = "platform")]
+    uncompressed: Arc<[CommonDir]>,
+    dirs: Arc<[RegisteredDir]>,
+    decode_cache: Option<Arc<Mutex<DecodeCache>>>,
+    max_input_len: Option<usize>,
+    max_escape_expansion: Option<usize>,
+    #[cfg(feature = "platform")]
+    interner: Option<Arc<Interner>>,
+    #[cfg(all(windows, feature = "short-names"))]
+    expand_short_names: bool,
+    stats: Option<Arc<StatsCollector>>,
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Codec {
+    pub fn new() -> Self {
+        CodecBuilder::default().build_valid()
+    }
+
+    pub fn builder() -> CodecBuilder {
+        CodecBuilder::default()
+    }
+
+    /// Returns the counts of the interner set up with [`CodecBuilder::intern_tokens`], if any.
+    #[cfg(feature = "platform")]
+    pub fn intern_stats(&self) -> Option<InternStats> {
+        self.interner.as_ref().map(|interner| interner.stats())
+    }
+
+    /// Returns the counts of the encoder set up with [`CodecBuilder::collect_stats`], if any.
+    pub fn stats(&self) -> Option<Stats> {
+        self.stats.as_ref().map(|stats| stats.stats())
+    }
+
+    /// Detects the platform recorded in an encoded `filename` with the same rules [`Codec::to_path`] uses to decode it.
+    #[cfg(feature = "platform")]
+    pub fn detect_platform_of_filename(&self, filename: impl AsRef<str>) -> Option<PlatformKind> {
+        if self.escaping_only {
+            return None;
+        }
+        let (i, platform) = Platform::parse_filename_platform(filename.as_ref()).ok()?;
+        let (i, _) = platform.parse_filename_lowercase_root(i).ok()?;
+        platform.parse_filename_prefix(i, &self.escaper, &self.dirs).ok()?;
+        Some(platform.kind)
+    }
+
+    pub fn to_path(&self, filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+        let (path, _) = self.to_path_and_counter(filename)?;
+        Ok(path)
+    }
+
+    pub fn to_path_from_str(&self, filename: impl AsRef<str>) -> Result<PathBuf, Error> {
+        let (path, _) = self.to_path_and_counter_from_str(filename)?;
+        Ok(path)
+    }
+
+    /// Decodes `filename` like [`Codec::to_path`], but borrows it as the path when it has no icons or escapes to
+    /// decode, like `report.txt`, saving the allocation in code listing many plain names.
+    pub fn to_path_cow<'a, S: AsRef<OsStr> + ?Sized>(&self, filename: &'a S) -> Result<Cow<'a, Path>, Error> {
+        let filename = filename.as_ref();
+        let Some(filename) = filename.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(filename.into()));
+        };
+        self.check_limits(filename)?;
+        // unknown icons are decoded as they are only with the default policy
+        #[cfg(feature = "platform")]
+        let plain = self.escaper.is_plain(filename) && (self.escaping_only || self.skip_unknown_icons(filename)?.len() == filename.len());
+        #[cfg(not(feature = "platform"))]
+        let plain = self.escaper.is_plain(filename);
+        if plain {
+            return Ok(Cow::Borrowed(Path::new(filename)));
+        }
+        Ok(Cow::Owned(self.to_path_from_str(filename)?))
+    }
+
+    /// Decodes `filename` like [`Codec::to_path`], also returning the counter suffix added by [`Codec::to_unique_filename_in`].
+    pub fn to_path_and_counter(&self, filename: impl AsRef<OsStr>) -> Result<(PathBuf, Option<u32>), Error> {
+        let filename = filename.as_ref();
+        let Some(filename) = filename.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(filename.into()));
+        };
+        self.to_path_and_counter_from_str(filename)
+    }
+
+    pub fn to_path_and_counter_from_str(&self, filename: impl AsRef<str>) -> Result<(PathBuf, Option<u32>), Error> {
+        let filename = filename.as_ref();
+        let Some(cache) = &self.decode_cache else {
+            return self.decode(filename);
+        };
+        if let Some(decoded) = cache.lock().unwrap_or_else(|err| err.into_inner()).get(filename) {
+            return Ok(decoded);
+        }
+        let (path, counter) = self.decode(filename)?;
+        cache.lock().unwrap_or_else(|err| err.into_inner()).insert(filename, path.clone(), counter);
+        Ok((path, counter))
+    }
+
+    /// Decodes `filename` like [`Codec::to_path`], but puts a path under the home directory of the encoded
+    /// user under the home directory of the current user instead, to restore artifacts onto another account or
+    /// machine, e.g. `🍎📄alice／x` to `/home/bob/Documents/x` for bob on Linux.
+    ///
+    /// See [`Codec::to_path_rehomed`] for the details. It's an [`Error::Io`] if the home directory of the current
+    /// user is unknown.
+    #[cfg(feature = "localize")]
+    pub fn to_path_localized(&self, filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+        let Some(home) = dirs::home_dir() else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "the home directory of the current user is unknown").into());
+        };
+        let filename = filename.as_ref();
+        let Some(filename) = filename.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(filename.into()));
+        };
+        self.to_path_rehomed(filename, home)
+    }
+
+    /// Decodes `filename` like [`Codec::to_path`], but puts a path under the home directory of the encoded
+    /// user under `home` instead, e.g. `🍎📄alice／x` with `/home/bob` to `/home/bob/Documents/x`.
+    ///
+    /// The rest of the path after the home is split at the separators of the encoded platform and joined with
+    /// the ones of this OS, and a trailing separator is dropped. Filenames of no home, like the ones of drives,
+    /// and paths whose home is relocated to outside of it are decoded as is.
+    #[cfg(feature = "platform")]
+    pub fn to_path_rehomed(&self, filename: impl AsRef<str>, home: impl AsRef<Path>) -> Result<PathBuf, Error> {
+        let filename = filename.as_ref();
+        let home = home.as_ref();
+        self.decode(filename)?;
+        let Some((path_name, target)) = self.split_link(filename) else {
+            return self.rehome_name(filename, home);
+        };
+        let mut path = self.rehome_name(path_name, home)?.into_os_string();
+        path.push(LINK_NOTATION);
+        path.push(self.rehome_name(target, home)?);
+        Ok(PathBuf::from(path))
+    }
+
+    /// Decodes `filename` like [`Codec::to_path`], but writes a Windows path under the home directory of the
+    /// encoded user with an environment variable instead, e.g. `💠📄alice＼x.txt` to
+    /// `%USERPROFILE%\Documents\x.txt`, for scripts to run under another account than the one encoded.
+    ///
+    /// The innermost of `%TEMP%` for `AppData\Local\Temp`, `%LOCALAPPDATA%` for `AppData\Local`, `%APPDATA%`
+    /// for `AppData\Roaming` and `%USERPROFILE%` for the home itself is used, matching the names
+    /// case-insensitively like Windows does. Paths of other platforms and paths of no home are decoded as is.
+    #[cfg(feature = "platform")]
+    pub fn to_path_with_env_vars(&self, filename: impl AsRef<str>) -> Result<PathBuf, Error> {
+        let filename = filename.as_ref();
+        self.decode(filename)?;
+        let Some((path_name, target)) = self.split_link(filename) else {
+            return self.env_var_name(filename);
+        };
+        let mut path = self.env_var_name(path_name)?.into_os_string();
+        path.push(LINK_NOTATION);
+        path.push(self.env_var_name(target)?);
+        Ok(PathBuf::from(path))
+    }
+
+    #[cfg(feature = "platform")]
+    fn env_var_name(&self, filename: &str) -> Result<PathBuf, Error> {
+        let (path, home_rest) = self.split_home(filename)?;
+        let Some((PlatformKind::Windows, rest)) = home_rest else {
+            return Ok(path);
+        };
+        let vars = [("TEMP", "\\AppData\\Local\\Temp"), ("LOCALAPPDATA", "\\AppData\\Local"), ("APPDATA", "\\AppData\\Roaming")];
+        let (var, rest) = vars.into_iter().find_map(|(var, dir)| {
+            let (head, tail) = (rest.get(..dir.len())?, rest.get(dir.len()..)?);
+            (head.eq_ignore_ascii_case(dir) && (tail.is_empty() || tail.starts_with(WINDOWS_SEP))).then_some((var, tail))
+        }).unwrap_or(("USERPROFILE", &rest));
+        Ok(PathBuf::from(format!("%{}%{}", var, rest)))
+    }
+
+    #[cfg(feature = "platform")]
+    fn rehome_name(&self, filename: &str, home: &Path) -> Result<PathBuf, Error> {
+        let (path, home_rest) = self.split_home(filename)?;
+        let Some((platform, rest)) = home_rest else {
+            return Ok(path);
+        };
+        let sep = match platform {
+            PlatformKind::Windows => '\\',
+            PlatformKind::Mac | PlatformKind::Linux => POSIX_SEP,
+        };
+        let mut rehomed = home.to_path_buf();
+        rehomed.extend(rest.split(sep).filter(|component| !component.is_empty()));
+        Ok(rehomed)
+    }
+
+    // the decoded path, with its platform and the rest after the home if it's under the home of the encoded user
+    #[cfg(feature = "platform")]
+    fn split_home(&self, filename: &str) -> Result<(PathBuf, Option<(PlatformKind, String)>), Error> {
+        let (path, _) = self.decode_name(filename)?;
+        let segments = self.parse(filename)?.segments;
+        let platform = segments.iter().find_map(|segment| match segment.kind {
+            SegmentKind::PlatformIcon(kind) => Some(kind),
+            _ => None,
+        });
+        let dir = segments.iter().find(|segment| matches!(segment.kind, SegmentKind::CommonDirIcon(dir) if dir != CommonDir::Drive));
+        let user = segments.iter().find(|segment| matches!(segment.kind, SegmentKind::UserToken(_)));
+        let (Some(platform), Some(dir), Some(user)) = (platform, dir, user) else {
+            return Ok((path, None));
+        };
+
+        // the home of the encoded user, decoded with the same platform, case and relocations
+        let (Some(head), Some(user)) = (filename.get(..dir.span.start), filename.get(user.span.clone())) else {
+            return Ok((path, None));
+        };
+        let (encoded_home, _) = self.decode_name(&format!("{}{}{}", head, HOME_ICON, user))?;
+        let rest = path.to_str().zip(encoded_home.to_str()).and_then(|(path, encoded_home)| path.strip_prefix(encoded_home)).map(str::to_string);
+        Ok((path, rest.map(|rest| (platform, rest))))
+    }
+
+    fn decode(&self, filename: &str) -> Result<(PathBuf, Option<u32>), Error> {
+        self.check_limits(filename)?;
+        let filename = self.strip_header(filename)?;
+        let Some((path_name, target)) = self.split_link(filename) else {
+            return self.decode_name(filename);
+        };
+        let (path, counter) = self.decode_name(path_name)?;
+        if counter.is_some() {
+            // a counter belongs to the end of the whole filename, not before the arrow
+            let input = path_name.rfind(COUNTER_ICON).and_then(|pos| filename.get(pos..)).unwrap_or_default();
+            return Err(Error::ParseError(nom::error::Error { input: input.to_string(), code: nom::error::ErrorKind::Eof }));
+        }
+        let (target, counter) = self.decode_name(target)?;
+        let mut path = path.into_os_string();
+        path.push(LINK_NOTATION);
+        path.push(target);
+        Ok((PathBuf::from(path), counter))
+    }
+
+    fn check_limits(&self, filename: &str) -> Result<(), Error> {
+        if let Some(max) = self.max_input_len {
+            if max < filename.len() {
+                return Err(Error::LimitExceeded(format!("the filename is {} bytes, over the limit of {}", filename.len(), max)));
+            }
+        }
+        if let Some(max) = self.max_escape_expansion {
+            let expansion = self.escaper.compact_expansion(filename);
+            if max < expansion {
+                return Err(Error::LimitExceeded(format!("the compact escapes expand to {} chars, over the limit of {}", expansion, max)));
+            }
+        }
+        Ok(())
+    }
+
+    // splits a filename encoded in the link notation at the arrow
+    fn split_link<'a>(&self, filename: &'a str) -> Option<(&'a str, &'a str)> {
+        if !self.link_notation {
+            return None;
+        }
+        filename.split_once(LINK_ICON)
+    }
+
+    fn decode_name(&self, filename: &str) -> Result<(PathBuf, Option<u32>), Error> {
+        let escaper = &self.escaper;
+
+        let i = filename;
+        #[cfg(feature = "platform")]
+        let (i, prefix, sep) = match Platform::parse_filename_platform(i) {
+            _ if self.escaping_only => (i, "".to_string(), POSIX_SEP),
+            Ok((i, platform)) => {
+                let (i, lowercase_root) = platform.parse_filename_lowercase_root(i)?;
+                let (i, mut prefix) = platform.parse_filename_prefix(i, escaper, &self.dirs)?;
+                if lowercase_root && self.case_restoration == CaseRestoration::Original {
+                    prefix = platform.lowercase_root(&prefix);
+                }
+                (i, prefix, platform.sep)
+            },
+            Err(_) => (self.skip_unknown_icons(i)?, "".to_string(), POSIX_SEP),
+        };
+        #[cfg(not(feature = "platform"))]
+        let (prefix, sep) = (String::new(), POSIX_SEP);
+        // these don't fail with the escaper design, but are errors rather than panics if they did
+        let (i, mut path) = escaper.unescape(i)?;
+        let (i, trailing_sep) = opt(Escaper::parse_trailing_sep)(i)?;
+        let (i, counter) = opt(Escaper::parse_counter)(i)?;
+        if !i.is_empty() {
+            return Err(Error::ParseError(nom::error::Error { input: i.to_string(), code: nom::error::ErrorKind::Eof }));
+        }
+
+        if trailing_sep.is_some() {
+            path.push(sep);
+        }
+        let path = prefix + &path;
+        #[cfg(feature = "platform")]
+        let path = match Self::relocate(&path, self.relocations.iter().map(|r| (&r.canonical, &r.actual))) {
+            Some(relocated) if !self.escaping_only => relocated,
+            _ => path,
+        };
+        Ok((PathBuf::from(path), counter))
+    }
+
+    // applies the unknown icon policy to the icon-like chars a filename of no platform starts with
+    #[cfg(feature = "platform")]
+    fn skip_unknown_icons<'a>(&self, i: &'a str) -> Result<&'a str, Error> {
+        let unknown = |c: char| {
+            is_icon_like(c) && !RESERVED_ICONS.contains(&c) && !self.dirs.iter().any(|dir| dir.icon == c) && !self.escaper.escaping_map.contains_key(&c)
+        };
+        // a variation selector, like the one of `☀️`, belongs to the icon before it
+        let mut rest = i;
+        while let Some(after) = rest.strip_prefix(unknown) {
+            rest = after.trim_start_matches('\u{FE0F}');
+        }
+        match self.unknown_icons {
+            _ if rest.len() == i.len() => Ok(i),
+            UnknownIconPolicy::PassThrough => Ok(i),
+            UnknownIconPolicy::Error => Err(Error::ParseError(nom::error::Error { input: i.to_string(), code: nom::error::ErrorKind::Char })),
+            UnknownIconPolicy::Strip => Ok(rest),
+        }
+    }
+
+    // replaces the first of the `(from, to)` dirs `path` is in, looking at longer dirs first
+    #[cfg(feature = "platform")]
+    fn relocate<'a>(path: &str, dirs: impl Iterator<Item = (&'a String, &'a String)>) -> Option<String> {
+        let mut dirs: Vec<_> = dirs.collect();
+        dirs.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        dirs.into_iter().find_map(|(from, to)| {
+            let rest = path.strip_prefix(from.as_str())?;
+            (rest.is_empty() || rest.starts_with([WINDOWS_SEP, POSIX_SEP])).then(|| to.clone() + rest)
+        })
+    }
+
+    pub fn to_filename(&self, path: impl AsRef<Path>) -> Result<String, Error> {
+        let path = path.as_ref();
+        let path = path.as_os_str();
+        let Some(path) = path.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(path.into()));
+        };
+        #[cfg(all(windows, feature = "short-names"))]
+        if self.expand_short_names && short_name_segments(path).next().is_some() {
+            if let Some(long) = short_names::long_path(path) {
+                return Ok(self.to_filename_from_str(long));
+            }
+        }
+        Ok(self.to_filename_from_str(path))
+    }
+
+    /// Encodes the path `components` make up, like the ones of [`Path::components`].
+    ///
+    /// The path is rebuilt from the prefix, root and names std parsed. So it's normalized the way `Components`
+    /// normalizes it. Repeated separators, `.` components after the first, and a trailing separator are dropped.
+    /// A Windows prefix is written in its usual form followed by `\` separators, where the verbatim ones of
+    /// disks and shares are written without `\\?\`, so `\\?\C:\Users\alice` and `C:/Users/alice` are
+    /// compressed like `C:\Users\alice`, and `\\?\UNC\server\share` like `\\server\share`.
+    pub fn to_filename_components(&self, components: Components<'_>) -> Result<String, Error> {
+        fn to_str(s: &OsStr) -> Result<&str, Error> {
+            s.to_str().ok_or_else(|| Error::CouldntEncodeToUtf8(s.into()))
+        }
+        let mut path = String::new();
+        let mut sep = MAIN_SEPARATOR;
+        // whether a name goes after a separator, unlike after a root or a relative prefix like `C:`
+        let mut after_name = false;
+        for component in components {
+            let name = match component {
+                Component::Prefix(prefix) => {
+                    sep = WINDOWS_SEP;
+                    match prefix.kind() {
+                        Prefix::Disk(disk) | Prefix::VerbatimDisk(disk) => path.extend([char::from(disk), ':']),
+                        Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                            path.push_str(&format!("{}{}{}{}{}", WINDOWS_SEP, WINDOWS_SEP, to_str(server)?, WINDOWS_SEP, to_str(share)?));
+                        },
+                        Prefix::DeviceNS(device) => path.push_str(&format!("{}{}.{}{}", WINDOWS_SEP, WINDOWS_SEP, WINDOWS_SEP, to_str(device)?)),
+                        Prefix::Verbatim(name) => path.push_str(&format!("{}{}?{}{}", WINDOWS_SEP, WINDOWS_SEP, WINDOWS_SEP, to_str(name)?)),
+                    }
+                    continue;
+                },
+                Component::RootDir => {
+                    path.push(sep);
+                    after_name = false;
+                    continue;
+                },
+                Component::CurDir => ".",
+                Component::ParentDir => "..",
+                Component::Normal(name) => to_str(name)?,
+            };
+            if after_name {
+                path.push(sep);
+            }
+            path.push_str(name);
+            after_name = true;
+        }
+        self.to_filename(path)
+    }
+
+    pub fn to_filename_from_str(&self, path: impl AsRef<str>) -> String {
+        let path = path.as_ref();
+        let filename = match path.split_once(LINK_NOTATION) {
+            Some((path, target)) if self.link_notation => {
+                let mut filename = self.encode_name(path);
+                filename.push(LINK_ICON);
+                filename + &self.encode_name(target)
+            },
+            _ => self.encode_name(path),
+        };
+        let filename = match &self.header {
+            Some(header) => header.to_string() + &filename,
+            None => filename,
+        };
+        if let Some(stats) = &self.stats {
+            stats.record_path(path, &filename);
+        }
+        filename
+    }
+
+    fn encode_name(&self, i: &str) -> String {
+        #[cfg(feature = "platform")]
+        let common_root = self.relocations.iter().any(|r| r.mount) && self.has_common_root_dir(i);
+        #[cfg(feature = "platform")]
+        let relocated = Self::relocate(i, self.relocations.iter().filter(|r| !(r.mount && common_root)).map(|r| (&r.actual, &r.canonical)));
+        #[cfg(feature = "platform")]
+        let i = relocated.as_deref().filter(|_| !self.escaping_only).unwrap_or(i);
+        #[cfg(feature = "platform")]
+        let sep = match Platform::sniff_path_platform(i) {
+            Ok((_, platform)) if !self.escaping_only => platform.sep,
+            _ => POSIX_SEP,
+        };
+        #[cfg(not(feature = "platform"))]
+        let sep = POSIX_SEP;
+        let collapsed;
+        let i = if self.collapse_separators {
+            collapsed = Self::collapse_separators(i, sep);
+            collapsed.as_str()
+        } else {
+            i
+        };
+        let (i, trailing_sep) = match self.trailing_separator {
+            TrailingSeparator::Preserve => (i, false),
+            TrailingSeparator::Strip => match i.trim_end_matches(sep) {
+                "" => (i, false),
+                trimmed => (trimmed, false),
+            },
+            TrailingSeparator::Mark => match i.strip_suffix(sep) {
+                Some(stripped) => (stripped, true),
+                None => (i, false),
+            },
+        };
+
+        #[cfg(feature = "platform")]
+        let mut filename = self.escape_with_prefix(i);
+        #[cfg(not(feature = "platform"))]
+        let mut filename = self.escaper.escape(i);
+        if trailing_sep {
+            filename.push(TRAILING_SEP_ICON);
+        }
+        if let Some(stats) = &self.stats {
+            #[cfg(feature = "platform")]
+            let prefix_compressed = !self.escaping_only && Platform::parse_filename_platform(&filename).is_ok();
+            #[cfg(not(feature = "platform"))]
+            let prefix_compressed = false;
+            stats.record_name(self.escaper.escaped_chars(&filename), prefix_compressed);
+        }
+        filename
+    }
+
+    // whether `i` is in a common root dir that's compressed to an icon, like a home
+    #[cfg(feature = "platform")]
+    fn has_common_root_dir(&self, i: &str) -> bool {
+        Platform::sniff_path_platform(i).is_ok_and(|(rest, platform)| platform.parse_path_prefix(rest, &self.dirs, &self.uncompressed, |_, _| ()).is_ok())
+    }
+
+    // escapes `i` with its OS and common dir compressed to icons
+    #[cfg(feature = "platform")]
+    fn escape_with_prefix(&self, i: &str) -> String {
+        let escaper = &self.escaper;
+        if self.escaping_only {
+            return escaper.escape(i);
+        }
+
+        let uppercased = match self.drive_letter_case {
+            DriveLetterCase::Preserve => None,
+            DriveLetterCase::Uppercase => Platform::uppercase_root(i),
+        };
+        let uppercased = uppercased.or_else(|| Platform::uppercase_mac_home(i)).filter(|i| Platform::sniff_path_platform(i).is_ok());
+
+        let Ok((rest, platform)) = Platform::sniff_path_platform(uppercased.as_deref().unwrap_or(i)) else {
+            return escaper.escape(i);
+        };
+
+        let mut prefix = String::new();
+        prefix.push(platform.prefix);
+        if uppercased.is_some() {
+            prefix.push(LOWERCASE_ROOT_ICON);
+        }
+
+        let prefixed = platform.parse_path_prefix(rest, &self.dirs, &self.uncompressed, |token, r| match &self.interner {
+            Some(interner) => r.push_str(&interner.escaped(token, |token| escaper.escape(token))),
+            None => r.push_str(&escaper.escape(token)),
+        });
+        // the original spelling is escaped as is when the common root dir isn't compressed
+        let Ok((rest, p)) = prefixed else {
+            return escaper.escape(i);
+        };
+        prefix.push_str(&p);
+        prefix + &escaper.escape(rest)
+    }
+
+    // collapses runs of `sep` except the leading one, which is meaningful in `\\\\server\\share` and `//host/path`
+    fn collapse_separators(i: &str, sep: char) -> String {
+        let rest = i.trim_start_matches(sep);
+        let mut r = i.strip_suffix(rest).unwrap_or_default().to_string();
+        for c in rest.chars() {
+            if c != sep || !r.ends_with(sep) {
+                r.push(c);
+            }
+        }
+        r
+    }
+
+    /// Encodes a single filename, like one supplied by a user, escaping its chars without any separator or
+    /// prefix logic.
+    ///
+    /// Windows reserved names like `CON` or `nul.txt` and a trailing dot or space, which Windows strips, get their
+    /// last char written as a compact escape, and so do the ASCII control chars the codec doesn't escape, like
+    /// `\u{1}` to `〔1〕`. So the result is a valid name on Windows, macOS and Linux, apart from its length, and
+    /// still decodes back with [`Codec::to_path`].
+    pub fn encode_basename(&self, name: impl AsRef<OsStr>) -> Result<String, Error> {
+        let name = name.as_ref();
+        let Some(name) = name.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(name.into()));
+        };
+        Ok(self.encode_basename_from_str(name))
+    }
+
+    pub fn encode_basename_from_str(&self, name: impl AsRef<str>) -> String {
+        let name = name.as_ref();
+        let stem = name.split('.').next().unwrap_or(name).trim_end_matches(' ');
+        let protected = if is_windows_reserved_name(stem) {
+            Some(stem.len())
+        } else if name.ends_with(['.', ' ']) {
+            Some(name.len())
+        } else {
+            None
+        };
+        let Some(end) = protected else {
+            return self.escape_basename_part(name);
+        };
+        let (head, tail) = name.split_at_checked(end).unwrap_or((name, ""));
+        let mut head = head.chars();
+        let Some(last) = head.next_back() else {
+            return self.escape_basename_part(name);
+        };
+        self.escape_basename_part(head.as_str()) + &Escaper::compact_escape(last, 1) + &self.escape_basename_part(tail)
+    }
+
+    // escapes `s` like a path, writing the control chars Windows rejects in names as compact escapes
+    fn escape_basename_part(&self, s: &str) -> String {
+        let mut r = String::new();
+        let mut start = 0;
+        for (n, c) in s.char_indices() {
+            if c.is_ascii_control() && !self.escaper.escaping_map.contains_key(&c) {
+                r.push_str(&self.escaper.escape(&s[start..n]));
+                r.push_str(&Escaper::compact_escape(c, 1));
+                start = n + c.len_utf8();
+            }
+        }
+        r + &self.escaper.escape(&s[start..])
+    }
+
+    /// Returns the length of the encoded filename of `path` in UTF-8 bytes.
+    pub fn encoded_len(&self, path: impl AsRef<Path>) -> Result<usize, Error> {
+        self.encoded_len_for(path, FsProfile::Utf8)
+    }
+
+    /// Returns the length of the encoded filename of `path` in the unit the `profile` filesystem counts.
+    pub fn encoded_len_for(&self, path: impl AsRef<Path>, profile: FsProfile) -> Result<usize, Error> {
+        Ok(profile.filename_len(&self.to_filename(path)?))
+    }
+
+    /// Reads the encoded artifacts in `dir` and yields `(artifact_path, decoded_original_path)` pairs.
+    ///
+    /// Entries that can't be read or decoded are reported as `(artifact_path, error)` pairs, or with `dir` itself
+    /// when it or an entry of it can't be read, so callers that only want the decodable ones can skip them with
+    /// `filter_map(Result::ok)`.
+    pub fn decode_dir(&self, dir: impl AsRef<Path>) -> impl Iterator<Item = Result<(PathBuf, PathBuf), (PathBuf, Error)>> {
+        let codec = self.clone();
+        let dir = dir.as_ref().to_path_buf();
+        let (entries, err) = match fs::read_dir(&dir) {
+            Ok(entries) => (Some(entries), None),
+            Err(err) => (None, Some((dir.clone(), Error::from(err)))),
+        };
+        err.map(Err).into_iter().chain(entries.into_iter().flatten().map(move |entry| {
+            let entry = entry.map_err(|err| (dir.clone(), Error::from(err)))?;
+            match codec.to_path(entry.file_name()) {
+                Ok(original) => Ok((entry.path(), original)),
+                Err(err) => Err((entry.path(), err)),
+            }
+        }))
+    }
+
+    /// Decodes a whole listing of encoded `names`, partitioning them into `(name, original_path)` pairs and
+    /// `(name, error)` pairs, both in the order given.
+    pub fn decode_batch<S: AsRef<str>>(&self, names: impl IntoIterator<Item = S>) -> DecodedBatch {
+        let mut decoded = Vec::new();
+        let mut errors = Vec::new();
+        for name in names {
+            let name = name.as_ref();
+            match self.to_path_from_str(name) {
+                Ok(path) => decoded.push((name.to_string(), path)),
+                Err(err) => errors.push((name.to_string(), err)),
+            }
+        }
+        (decoded, errors)
+    }
+
+    /// Returns a filename for `path` that is free to use in `dir`, and the counter suffix it had to add if any.
+    ///
+    /// An existing file with the encoded name is reused when it decodes to `path`. When it holds a different
+    /// original (e.g. a case variant on a case-insensitive filesystem), a counter suffix is appended, which
+    /// [`Codec::to_path`] strips again.
+    pub fn to_unique_filename_in(&self, dir: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<(String, Option<u32>), Error> {
+        let dir = dir.as_ref();
+        let path = path.as_ref();
+        let filename = self.to_filename(path)?;
+
+        let mut names = None;
+        self.choose_unique_filename(path, &filename, |candidate| {
+            match fs::symlink_metadata(dir.join(candidate)) {
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let names = match &mut names {
+                Some(names) => names,
+                None => names.insert(fs::read_dir(dir)?.map(|entry| Ok(entry?.file_name())).collect::<Result<Vec<_>, io::Error>>()?),
+            };
+            let stored = names.iter().find(|name| name.as_os_str() == candidate)
+                .or_else(|| names.iter().find(|name| name.to_str().map(str::to_lowercase) == Some(candidate.to_lowercase())));
+            Ok(Some(stored.cloned().unwrap_or_default()))
+        })
+    }
+
+    fn choose_unique_filename(&self, path: &Path, filename: &str, mut stored_name: impl FnMut(&str) -> Result<Option<OsString>, Error>) -> Result<(String, Option<u32>), Error> {
+        for counter in std::iter::once(None).chain((1..=u32::MAX).map(Some)) {
+            let candidate = match counter {
+                Some(counter) => format!("{}{}{}", filename, COUNTER_ICON, counter),
+                None => filename.to_string(),
+            };
+            let Some(stored) = stored_name(&candidate)? else {
+                return Ok((candidate, counter));
+            };
+            if let Ok((original, _)) = self.to_path_and_counter(stored) {
+                if original == path {
+                    return Ok((candidate, counter));
+                }
+            }
+        }
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, "every counter is taken").into())
+    }
+
+    /// Writes `bytes` into `dir` under the encoded name of `path` and returns the written artifact path.
+    ///
+    /// The bytes go to a temp file in `dir` first, which is then renamed to the encoded name, so readers never
+    /// observe a partially written artifact.
+    pub fn write_encoded(&self, dir: impl AsRef<Path>, path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> Result<PathBuf, Error> {
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = dir.as_ref();
+        let artifact_path = dir.join(self.to_filename(path)?);
+        let temp_path = dir.join(format!(".path_to_unicode_filename.{}.{}.tmp", process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+        let result = fs::OpenOptions::new().write(true).create_new(true).open(&temp_path).and_then(|mut file| {
+            file.write_all(bytes.as_ref())?;
+            file.sync_all()
+        }).and_then(|_| fs::rename(&temp_path, &artifact_path));
+
+        if let Err(err) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err.into());
+        }
+        Ok(artifact_path)
+    }
+}
+
+/// Detects the platform of `path` with the same heuristics [`to_filename`] uses to pick an OS icon.
+#[cfg(feature = "platform")]
+pub fn detect_platform(path: impl AsRef<Path>) -> Option<PlatformKind> {
+    let path = path.as_ref().as_os_str().to_str()?;
+    let uppercased = Platform::uppercase_mac_home(path);
+    let (_, platform) = Platform::sniff_path_platform(uppercased.as_deref().unwrap_or(path)).ok()?;
+    Some(platform.kind)
+}
+
+/// Detects the platform recorded in an encoded `filename` with the same rules [`to_path`] uses to decode it.
+#[cfg(feature = "platform")]
+pub fn detect_platform_of_filename(filename: impl AsRef<str>) -> Option<PlatformKind> {
+    Codec::new().detect_platform_of_filename(filename)
+}
+
+pub fn to_path(filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+    Codec::new().to_path(filename)
+}
+
+pub fn to_path_from_str(filename: impl AsRef<str>) -> Result<PathBuf, Error> {
+    Codec::new().to_path_from_str(filename)
+}
+
+/// Decodes a filename with the settings in its header, or with the default settings if it has none.
+///
+/// See [`Codec::decode_auto`].
+pub fn decode_auto(filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+    Codec::new().decode_auto(filename)
+}
+
+/// Decodes `filename`, borrowing it when there's nothing to decode.
+///
+/// See [`Codec::to_path_cow`].
+pub fn to_path_cow<S: AsRef<OsStr> + ?Sized>(filename: &S) -> Result<Cow<'_, Path>, Error> {
+    Codec::new().to_path_cow(filename)
+}
+
+/// Decodes `filename` like [`to_path`], also returning the counter suffix added by [`to_unique_filename_in`].
+pub fn to_path_and_counter(filename: impl AsRef<OsStr>) -> Result<(PathBuf, Option<u32>), Error> {
+    Codec::new().to_path_and_counter(filename)
+}
+
+pub fn to_path_and_counter_from_str(filename: impl AsRef<str>) -> Result<(PathBuf, Option<u32>), Error> {
+    Codec::new().to_path_and_counter_from_str(filename)
+}
+
+/// Decodes `filename`, putting a path under the home directory of the encoded user under the one of the
+/// current user instead.
+///
+/// See [`Codec::to_path_localized`].
+#[cfg(feature = "localize")]
+pub fn to_path_localized(filename: impl AsRef<OsStr>) -> Result<PathBuf, Error> {
+    Codec::new().to_path_localized(filename)
+}
+
+/// Decodes `filename`, writing a Windows path under the home directory of the encoded user with an environment
+/// variable instead.
+///
+/// See [`Codec::to_path_with_env_vars`].
+#[cfg(feature = "platform")]
+pub fn to_path_with_env_vars(filename: impl AsRef<str>) -> Result<PathBuf, Error> {
+    Codec::new().to_path_with_env_vars(filename)
+}
+
+/// Decodes `filename`, putting a path under the home directory of the encoded user under `home` instead.
+///
+/// See [`Codec::to_path_rehomed`].
+#[cfg(feature = "platform")]
+pub fn to_path_rehomed(filename: impl AsRef<str>, home: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    Codec::new().to_path_rehomed(filename, home)
+}
+
+pub fn to_filename(path: impl AsRef<Path>) -> Result<String, Error> {
+    Codec::new().to_filename(path)
+}
+
+pub fn to_filename_from_str(path: impl AsRef<str>) -> String {
+    Codec::new().to_filename_from_str(path)
+}
+
+/// Encodes the path `components` make up.
+///
+/// See [`Codec::to_filename_components`].
+pub fn to_filename_components(components: Components<'_>) -> Result<String, Error> {
+    Codec::new().to_filename_components(components)
+}
+
+/// Encodes a single filename, escaping its chars and the names Windows reserves.
+///
+/// See [`Codec::encode_basename`].
+pub fn encode_basename(name: impl AsRef<OsStr>) -> Result<String, Error> {
+    Codec::new().encode_basename(name)
+}
+
+pub fn encode_basename_from_str(name: impl AsRef<str>) -> String {
+    Codec::new().encode_basename_from_str(name)
+}
+
+// the device names Windows reserves in any directory and with any extension, case-insensitively
+fn is_windows_reserved_name(stem: &str) -> bool {
+    let stem = stem.to_ascii_uppercase();
+    match stem.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" => true,
+        _ => match stem.as_bytes() {
+            [b'C', b'O', b'M', digit] | [b'L', b'P', b'T', digit] => digit.is_ascii_digit(),
+            _ => false,
+        },
+    }
+}
+
+/// Returns the length of the encoded filename of `path` in UTF-8 bytes.
+pub fn encoded_len(path: impl AsRef<Path>) -> Result<usize, Error> {
+    Codec::new().encoded_len(path)
+}
+
+/// Returns the length of the encoded filename of `path` in the unit the `profile` filesystem counts.
+pub fn encoded_len_for(path: impl AsRef<Path>, profile: FsProfile) -> Result<usize, Error> {
+    Codec::new().encoded_len_for(path, profile)
+}
+
+/// Reads the encoded artifacts in `dir` and yields `(artifact_path, decoded_original_path)` pairs.
+///
+/// See [`Codec::decode_dir`].
+pub fn decode_dir(dir: impl AsRef<Path>) -> impl Iterator<Item = Result<(PathBuf, PathBuf), (PathBuf, Error)>> {
+    Codec::new().decode_dir(dir)
+}
+
+/// Decodes a whole listing of encoded `names`, partitioning successes and failures.
+///
+/// See [`Codec::decode_batch`].
+pub fn decode_batch<S: AsRef<str>>(names: impl IntoIterator<Item = S>) -> DecodedBatch {
+    Codec::new().decode_batch(names)
+}
+
+/// Returns a filename for `path` that is free to use in `dir`, and the counter suffix it had to add if any.
+///
+/// See [`Codec::to_unique_filename_in`].
+pub fn to_unique_filename_in(dir: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<(String, Option<u32>), Error> {
+    Codec::new().to_unique_filename_in(dir, path)
+}
+
+/// Writes `bytes` into `dir` under the encoded name of `path` and returns the written artifact path.
+///
+/// See [`Codec::write_encoded`].
+pub fn write_encoded(dir: impl AsRef<Path>, path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> Result<PathBuf, Error> {
+    Codec::new().write_encoded(dir, path, bytes)
+}
+
+/// Flattens encoded filenames, like of a directory and a file in it, into one filename joined with `🔗`.
+///
+/// The encoder escapes `🔗` to `📎`, so [`split_encoded`] gets the names back. A name with a literal `🔗`,
+/// which no encoder writes, is an [`Error::ParseError`] from the `🔗` on. An empty slice is joined to the same
+/// string as a single empty name.
+///
+/// ```rust
+/// use path_to_unicode_filename::*;
+///
+/// let joined = join_encoded(&["／tmp／a📎b", "file.txt"]).unwrap();
+/// assert_eq!(joined, "／tmp／a📎b🔗file.txt");
+/// assert_eq!(split_encoded(&joined), vec!["／tmp／a📎b", "file.txt"]);
+/// assert!(join_encoded(&["／tmp／a🔗b"]).is_err());
+/// ```
+pub fn join_encoded(names: &[impl AsRef<str>]) -> Result<String, Error> {
+    let names: Vec<_> = names.iter().map(AsRef::as_ref).collect();
+    for name in &names {
+        if let Some(pos) = name.find(JOINER_ICON) {
+            return Err(Error::ParseError(nom::error::Error { input: name[pos..].to_string(), code: nom::error::ErrorKind::Char }));
+        }
+    }
+    Ok(names.join(&JOINER_ICON.to_string()))
+}
+
+/// Splits a filename made by [`join_encoded`] back into the encoded filenames.
+pub fn split_encoded(joined: impl AsRef<str>) -> Vec<String> {
+    joined.as_ref().split(JOINER_ICON).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ucd::Codepoint;
+    use ucd::tables::misc::EastAsianWidth::*;
+    use nom::{
+        Needed,
+        error::ErrorKind,
+    };
+
+    #[test]
+    fn check_chars() {
+        assert_eq!(ESCAPE_TARGET_CHARS.chars().collect::<Vec<_>>().len(), ESCAPED_CHARS.chars().collect::<Vec<_>>().len());
+        for c in ESCAPED_CHARS.chars() {
+            assert_explicit_width(c);
+        }
+
+        assert_explicit_width(MAC_ICON);
+        assert_explicit_width(LINUX_ICON);
+        assert_explicit_width(WINDOWS_ICON);
+
+        assert_explicit_width(HOME_ICON);
+        assert_explicit_width(MUSIC_ICON);
+        assert_explicit_width(APP_DATA_ICON);
+        assert_explicit_width(DESKTOP_ICON);
+        assert_explicit_width(DOCUMENTS_ICON);
+        assert_explicit_width(DOWNLOADS_ICON);
+        assert_explicit_width(PICTURES_ICON);
+        assert_explicit_width(VIDEOS_ICON);
+        assert_explicit_width(CACHES_ICON);
+        assert_explicit_width(DRIVE_ICON);
+
+        assert_explicit_width(COUNTER_ICON);
+        assert_explicit_width(TRAILING_SEP_ICON);
+        assert_explicit_width(LOWERCASE_ROOT_ICON);
+        assert_explicit_width(JOINER_ICON);
+        assert_explicit_width(LINK_ICON);
+        assert_explicit_width(HEADER_ESCAPED_ICON);
+        assert_explicit_width(COMPACT_ESCAPE_OPEN);
+        assert_explicit_width(COMPACT_ESCAPE_CLOSE);
+    }
+
+    fn assert_explicit_width(c: char) {
+        let w = c.east_asian_width();
+        assert!(w == Narrow || w == Wide || w == HalfWidth || w == FullWidth);
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn it_works() {
+        let pairs = [
+            ("/", "／"),
+            ("🍎", "🍏"),
+            ("/tmp", "／tmp"),
+            ("/media/disk001/file.txt", "🐧🥞disk001／file.txt"),
+            ("C:\\file.txt", "💠🥞C＼file.txt"),
+            ("C:\\Users\\alice\\file.txt", "💠🏠alice＼file.txt"),
+            ("C:\\Users\\alice\\Music\\file.mp3", "💠🎵alice＼file.mp3"),
+            ("/Users/alice/Library/Application Support", "🍎💾alice"),
+            ("/home/alice/Desktop/", "🐧🔝alice／"),
+            ("/home/alice/Documents/file.doc", "🐧📄alice／file.doc"),
+            ("/Users/alice/Downloads/file.txt", "🍎⏬alice／file.txt"),
+            ("C:\\Users\\alice\\Pictures\\file.jpg", "💠🎨alice＼file.jpg"),
+            ("/home/alice/Videos/file.mp4", "🐧🎥alice／file.mp4"),
+            ("/Volumes/disk001/file.txt", "🍎🥞disk001／file.txt"),
+            ("/Users/alice/Library/Caches/com.example.app", "🍎📦alice／com.example.app"),
+            ("/home/alice/.cache/pip", "🐧📦alice／pip"),
+            ("/home/alice/.cachedir", "🐧🏠alice／.cachedir"),
+            ("C:\\Users\\alice\\AppData\\Local\\Vendor\\App\\cache", "💠💾alice＼Vendor＼App＼cache"),
+            ("platform_icon_🍎_test", "platform_icon_🍏_test"),
+            ("platform_icon_🐧_test", "platform_icon_🐤_test"),
+            ("platform_icon_💠_test", "platform_icon_🚪_test"),
+            ("all_escape_targets_\0\\/:*?\"<>|🍎🐧💠_test", "all_escape_targets_〇＼／：＊？＂＜＞｜🍏🐤🚪_test"),
+            ("all_escape_escaped_chars_〇＼／：＊？＂＜＞｜🍏🐤🚪_test", "all_escape_escaped_chars_〇〇＼＼／／：：＊＊？？＂＂＜＜＞＞｜｜🍏🍏🐤🐤🚪🚪_test"),
+            ("/Volumes/disk🍎001/file.txt", "🍎🥞disk🍏001／file.txt"),
+            ("/Volumes/disk🐤001/file.txt", "🍎🥞disk🐤🐤001／file.txt"),
+            ("counter_icon_🔢1", "counter_icon_🔣1"),
+            ("/tmp/📂/📁", "／tmp／📁／📁📁"),
+            ("/tmp/line\nfeed\tand\r\nothers\x0b\x0c", "／tmp／line〷feed⏩and🔙〷others🔽📃"),
+            ("/tmp/〷⏩🔽📃🔙", "／tmp／〷〷⏩⏩🔽🔽📃📃🔙🔙"),
+        ];
+
+        for (path, filename) in pairs {
+            {
+                let filename = OsString::from(filename);
+                assert_eq!(to_path(filename).unwrap(), PathBuf::from(path));
+            }
+            {
+                let path = PathBuf::from(path);
+                assert_eq!(to_filename(path).unwrap(), filename);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn detect() {
+        assert_eq!(detect_platform("/Users/alice/file.txt"), Some(PlatformKind::Mac));
+        assert_eq!(detect_platform("/media/disk001"), Some(PlatformKind::Linux));
+        assert_eq!(detect_platform("C:\\Users\\alice\\Music"), Some(PlatformKind::Windows));
+        assert_eq!(detect_platform("/tmp/file.txt"), None);
+        assert_eq!(detect_platform("/Users/"), None);
+
+        assert_eq!(detect_platform_of_filename("🍎💾alice"), Some(PlatformKind::Mac));
+        assert_eq!(detect_platform_of_filename("🐧🥞disk001／file.txt"), Some(PlatformKind::Linux));
+        assert_eq!(detect_platform_of_filename("💠🥞C＼file.txt"), Some(PlatformKind::Windows));
+        assert_eq!(detect_platform_of_filename("／tmp／file.txt"), None);
+        assert_eq!(detect_platform_of_filename("🍎invalid"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn decode_dir_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        fs::write(dir.join("／tmp／file.txt"), "").unwrap();
+        fs::write(dir.join("🍎🎨bob／file.png"), "").unwrap();
+        fs::write(dir.join("🍎invalid"), "").unwrap();
+
+        let (mut decoded, errors): (Vec<_>, Vec<_>) = decode_dir(&dir).partition(Result::is_ok);
+        decoded.sort_by_key(|r| r.as_ref().unwrap().1.clone());
+        assert_eq!(decoded, vec![
+            Ok((dir.join("🍎🎨bob／file.png"), PathBuf::from("/Users/bob/Pictures/file.png"))),
+            Ok((dir.join("／tmp／file.txt"), PathBuf::from("/tmp/file.txt"))),
+        ]);
+        assert_eq!(errors, vec![Err((dir.join("🍎invalid"), Error::ParseError(nom::error::Error { input: "invalid".into(), code: ErrorKind::Char })))]);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(decode_dir(&dir).collect::<Vec<_>>(), vec![Err((dir.clone(), Error::Io(io::ErrorKind::NotFound.into())))]);
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn relocated_dirs() {
+        let mut builder = Codec::builder();
+        builder.relocations = vec![
+            Relocation { canonical: "C:\\Users\\alice".into(), actual: "E:\\alice".into(), mount: false },
+            Relocation { canonical: "C:\\Users\\alice\\Documents".into(), actual: "D:\\Docs".into(), mount: false },
+        ];
+        let codec = builder.clone().build().unwrap();
+        let pairs = [
+            ("D:\\Docs\\x.txt", "💠📄alice＼x.txt"),
+            ("D:\\Docs", "💠📄alice"),
+            ("E:\\alice\\Music", "💠🎵alice"),
+            ("D:\\Docsx", "💠🥞D＼Docsx"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+        }
+        assert_eq!(codec.to_path("💠🏠alice＼Documents"), Ok("D:\\Docs".into()));
+        assert_eq!(to_filename("D:\\Docs"), Ok("💠🥞D＼Docs".into()));
+
+        // the relocations of this machine are named, not held, by the config string
+        let config = codec.to_config_string();
+        assert!(config.contains(";local=known-folders;"));
+        assert_eq!(Codec::from_config_string(config).err(), Some(Error::InvalidConfig("the config depends on the known-folders of the machine it was made on".into())));
+        assert_eq!(builder.header(true).build().err(), Some(Error::InvalidConfig("a header can't hold the known-folders of this machine".into())));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn batch() {
+        let (decoded, errors) = decode_batch(["／tmp／a", "🍎invalid", "🍎🎨bob", "💠📦x"]);
+        assert_eq!(decoded, vec![("／tmp／a".into(), PathBuf::from("/tmp/a")), ("🍎🎨bob".into(), PathBuf::from("/Users/bob/Pictures"))]);
+        assert_eq!(errors, vec![
+            ("🍎invalid".into(), Error::ParseError(nom::error::Error { input: "invalid".into(), code: ErrorKind::Char })),
+            ("💠📦x".into(), Error::ParseError(nom::error::Error { input: "📦x".into(), code: ErrorKind::Char })),
+        ]);
+        assert_eq!(decode_batch(Vec::<String>::new()), (vec![], vec![]));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn unique_filename() {
+        assert_eq!(to_path_and_counter("／tmp／file.txt🔢3"), Ok((PathBuf::from("/tmp/file.txt"), Some(3))));
+        assert_eq!(to_path_and_counter("🍎🏠alice🔢12"), Ok((PathBuf::from("/Users/alice"), Some(12))));
+        assert_eq!(to_path_and_counter("／tmp／🔣🔣🔢1"), Ok((PathBuf::from("/tmp/🔣"), Some(1))));
+        assert_eq!(to_path_and_counter("／tmp／🔢1x"), Ok((PathBuf::from("/tmp/🔢1x"), None)));
+        assert_eq!(to_path("／tmp／file.txt🔢3"), Ok(PathBuf::from("/tmp/file.txt")));
+        // 0.1 kept 🔣 as it is
+        assert_eq!(to_path("a🔣3"), Ok(PathBuf::from("a🔢3")));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(to_unique_filename_in(&dir, "/tmp/file.txt"), Ok(("／tmp／file.txt".into(), None)));
+        fs::write(dir.path().join("／tmp／file.txt"), "").unwrap();
+        assert_eq!(to_unique_filename_in(&dir, "/tmp/file.txt"), Ok(("／tmp／file.txt".into(), None)));
+
+        // simulates a case-insensitive filesystem
+        let stored_name = |stored: &'static [&'static str]| move |candidate: &str| {
+            Ok(stored.iter().find(|name| name.to_lowercase() == candidate.to_lowercase()).map(OsString::from))
+        };
+        assert_eq!(Codec::new().choose_unique_filename(Path::new("/tmp/A"), "／tmp／A", stored_name(&["／tmp／A"])), Ok(("／tmp／A".into(), None)));
+        assert_eq!(Codec::new().choose_unique_filename(Path::new("/tmp/a"), "／tmp／a", stored_name(&["／tmp／A"])), Ok(("／tmp／a🔢1".into(), Some(1))));
+        assert_eq!(Codec::new().choose_unique_filename(Path::new("/tmp/a"), "／tmp／a", stored_name(&["／tmp／A", "／tmp／a🔢1"])), Ok(("／tmp／a🔢1".into(), Some(1))));
+        assert_eq!(Codec::new().choose_unique_filename(Path::new("/tmp/a"), "／tmp／a", stored_name(&["／tmp／A", "／tmp／A🔢1"])), Ok(("／tmp／a🔢2".into(), Some(2))));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn write_encoded_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        assert_eq!(write_encoded(&dir, "/Users/bob/Pictures/file.png", "first"), Ok(dir.join("🍎🎨bob／file.png")));
+        assert_eq!(write_encoded(&dir, "/Users/bob/Pictures/file.png", b"second"), Ok(dir.join("🍎🎨bob／file.png")));
+        assert_eq!(fs::read_to_string(dir.join("🍎🎨bob／file.png")).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(write_encoded(&dir, "/tmp/file.txt", "data"), Err(Error::Io(io::ErrorKind::NotFound.into())));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn spaces() {
+        let pairs = [
+            (SpaceReplacement::Keep, "/home/alice/my file.txt", "🐧🏠alice／my file.txt"),
+            (SpaceReplacement::Keep, "/tmp/␣ ", "／tmp／␣ "),
+            (SpaceReplacement::OpenBox, "/home/alice/my file.txt", "🐧🏠alice／my␣file.txt"),
+            (SpaceReplacement::OpenBox, "/home/my alice/my␣file.txt", "🐧🏠my␣alice／my␣␣file.txt"),
+            (SpaceReplacement::NoBreakSpace, "/tmp/a b\u{a0}", "／tmp／a\u{a0}b\u{a0}\u{a0}"),
+            (SpaceReplacement::IdeographicSpace, "C:\\Users\\my alice\\a b", "💠🏠my\u{3000}alice＼a\u{3000}b"),
+        ];
+
+        for (spaces, path, filename) in pairs {
+            let codec = Codec::builder().spaces(spaces).build().unwrap();
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(PathBuf::from(path)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn lengths() {
+        // "／tmp／" is 9 bytes, 5 UTF-16 units
+        assert_eq!(encoded_len("/tmp/a"), Ok(10));
+        assert_eq!(encoded_len_for("/tmp/a", FsProfile::Utf8), Ok(10));
+        assert_eq!(encoded_len_for("/tmp/a", FsProfile::Ntfs), Ok(6));
+        assert_eq!(encoded_len_for("/tmp/a", FsProfile::HfsPlus), Ok(10));
+
+        // "🍎" is out of the BMP, "が" is decomposed to "か" and U+3099 in NFD
+        assert_eq!(encoded_len_for("/Users/alice/が", FsProfile::Utf8), Ok(4 + 4 + 5 + 3 + 3));
+        assert_eq!(encoded_len_for("/Users/alice/が", FsProfile::Ntfs), Ok(2 + 2 + 5 + 1 + 1));
+        assert_eq!(encoded_len_for("/Users/alice/が", FsProfile::HfsPlus), Ok(4 + 4 + 5 + 3 + 3 + 3));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn trailing_separators() {
+        let pairs = [
+            (TrailingSeparator::Strip, "/home/alice/Desktop/", "🐧🔝alice", "/home/alice/Desktop"),
+            (TrailingSeparator::Strip, "/tmp//", "／tmp", "/tmp"),
+            (TrailingSeparator::Strip, "/", "／", "/"),
+            (TrailingSeparator::Strip, "C:\\Users\\alice\\", "💠🏠alice", "C:\\Users\\alice"),
+            (TrailingSeparator::Strip, "dir\\", "dir＼", "dir\\"),
+            (TrailingSeparator::Mark, "/home/alice/Desktop/", "🐧🔝alice📂", "/home/alice/Desktop/"),
+            (TrailingSeparator::Mark, "/home/alice/Desktop", "🐧🔝alice", "/home/alice/Desktop"),
+            (TrailingSeparator::Mark, "/tmp//", "／tmp／📂", "/tmp//"),
+            (TrailingSeparator::Mark, "/", "📂", "/"),
+            (TrailingSeparator::Mark, "C:\\Users\\alice\\", "💠🏠alice📂", "C:\\Users\\alice\\"),
+            (TrailingSeparator::Mark, "C:\\Users\\alice/", "💠🏠alice／", "C:\\Users\\alice/"),
+            (TrailingSeparator::Mark, "/tmp/📂/", "／tmp／📁📂", "/tmp/📂/"),
+        ];
+
+        for (trailing_separator, path, filename, decoded) in pairs {
+            let codec = Codec::builder().trailing_separator(trailing_separator).build().unwrap();
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(PathBuf::from(decoded)));
+        }
+
+        assert_eq!(to_path_and_counter("🐧🔝alice📂🔢2"), Ok((PathBuf::from("/home/alice/Desktop/"), Some(2))));
+        assert_eq!(to_path("／tmp📂x"), Ok(PathBuf::from("/tmp📂x")));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn collapse_separators() {
+        let pairs = [
+            ("/home//alice///file", "🐧🏠alice／file", "/home/alice/file"),
+            ("/tmp//a", "／tmp／a", "/tmp/a"),
+            ("C:\\\\Users\\alice", "💠🏠alice", "C:\\Users\\alice"),
+        ];
+
+        let codec = Codec::builder().collapse_separators(true).build().unwrap();
+        for (path, filename, decoded) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(PathBuf::from(decoded)));
+        }
+
+        // leading runs are kept
+        assert_eq!(codec.to_filename("//host//path"), Ok("／／host／path".into()));
+        assert_eq!(codec.to_filename("\\\\server\\\\share"), Ok("＼＼server＼＼share".into()));
+
+        let codec = Codec::builder().collapse_separators(true).trailing_separator(TrailingSeparator::Strip).build().unwrap();
+        assert_eq!(codec.to_filename("/home//alice//Desktop//"), Ok("🐧🔝alice".into()));
+        assert_eq!(to_filename("/home//alice///file"), Ok("／home／／alice／／／file".into()));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn volume_guid() {
+        let pairs = [
+            ("\\\\?\\Volume{0a1b2c3d-4e5f-6789-abcd-ef0123456789}\\backup\\file.txt", "💠🥞{0a1b2c3d-4e5f-6789-abcd-ef0123456789}＼backup＼file.txt"),
+            ("\\\\?\\Volume{0A1B2C3D-4E5F-6789-ABCD-EF0123456789}\\", "💠🥞{0A1B2C3D-4E5F-6789-ABCD-EF0123456789}＼"),
+            ("\\\\?\\Volume{0a1b2c3d-4e5f-6789-abcd-ef0123456789}", "💠🥞{0a1b2c3d-4e5f-6789-abcd-ef0123456789}"),
+        ];
+
+        for (path, filename) in pairs {
+            assert_eq!(to_filename(path), Ok(filename.into()));
+            assert_eq!(to_path(filename), Ok(PathBuf::from(path)));
+        }
+        assert_eq!(to_filename("\\\\?\\Volume{not-a-guid}\\x"), Ok("＼＼？＼Volume{not-a-guid}＼x".into()));
+        assert_eq!(to_filename("\\\\?\\Volume{0a1b}x"), Ok("＼＼？＼Volume{0a1b}x".into()));
+        assert_eq!(detect_platform("\\\\?\\Volume{0a1b2c3d-4e5f-6789-abcd-ef0123456789}\\x"), Some(PlatformKind::Windows));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn drive_letter_case() {
+        let pairs = [
+            ("c:\\x", "💠🔡🥞C＼x", "C:\\x"),
+            ("C:\\x", "💠🥞C＼x", "C:\\x"),
+            ("c:", "💠🔡🥞C", "C:"),
+            ("c:\\Users\\alice\\Documents\\x", "💠🔡📄alice＼x", "C:\\Users\\alice\\Documents\\x"),
+            ("é:\\x", "💠🥞é＼x", "é:\\x"),
+            ("/tmp/c:", "／tmp／c：", "/tmp/c:"),
+        ];
+
+        let codec = Codec::builder().drive_letter_case(DriveLetterCase::Uppercase).build().unwrap();
+        let canonical_codec = Codec::builder().drive_letter_case(DriveLetterCase::Uppercase).case_restoration(CaseRestoration::Canonical).build().unwrap();
+        for (path, filename, canonical) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(PathBuf::from(path)));
+            assert_eq!(canonical_codec.to_path(filename), Ok(PathBuf::from(canonical)));
+        }
+
+        assert_eq!(to_filename("c:\\x"), Ok("💠🥞c＼x".into()));
+        assert_eq!(to_path("💠🔡🥞C＼x"), Ok(PathBuf::from("c:\\x")));
+        assert_eq!(detect_platform_of_filename("💠🔡🥞C＼x"), Some(PlatformKind::Windows));
+        assert_eq!(to_path("🐧🔡🏠alice"), Err(Error::ParseError(nom::error::Error { input: "🔡🏠alice".into(), code: ErrorKind::Char })));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn lowercase_mac_home() {
+        let pairs = [
+            ("/users/alice", "🍎🔡🏠alice", "/Users/alice"),
+            ("/users/alice/Documents/x", "🍎🔡📄alice／x", "/Users/alice/Documents/x"),
+            ("/Users/alice", "🍎🏠alice", "/Users/alice"),
+            ("/users/", "／users／", "/users/"),
+            ("/USERS/alice", "／USERS／alice", "/USERS/alice"),
+        ];
+
+        let canonical_codec = Codec::builder().case_restoration(CaseRestoration::Canonical).build().unwrap();
+        for (path, filename, canonical) in pairs {
+            assert_eq!(to_filename(path), Ok(filename.into()));
+            assert_eq!(to_path(filename), Ok(PathBuf::from(path)));
+            assert_eq!(canonical_codec.to_path(filename), Ok(PathBuf::from(canonical)));
+        }
+        assert_eq!(detect_platform("/users/alice"), Some(PlatformKind::Mac));
+        assert_eq!(detect_platform("/users/"), None);
+        assert_eq!(to_path("🍎🔡🥞disk"), Ok(PathBuf::from("/Volumes/disk")));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn registered_dirs() {
+        let codec = Codec::builder().register_dir('🚧', "Projects").register_dir('🦀', "rust").build().unwrap();
+        let pairs = [
+            ("/Users/alice/Documents/Projects", "🍎📄🚧alice"),
+            ("/Users/alice/Documents/Projects/rust/file.rs", "🍎📄🚧🦀alice／file.rs"),
+            ("/Users/alice/Documents/rust/Projects/rust", "🍎📄🦀🚧🦀alice"),
+            ("/Users/alice/Documents/Projects/Projects", "🍎📄🚧alice／Projects"),
+            ("/Users/alice/Documents/Projectsx", "🍎📄alice／Projectsx"),
+            ("/home/alice/Projects/x", "🐧🏠🚧alice／x"),
+            ("/media/disk/rust", "🐧🥞🦀disk"),
+            ("C:\\Projects\\x", "💠🥞🚧C＼x"),
+            ("/Users/🚧alice/Documents/Projects", "🍎📄🚧🚧🚧alice"),
+            ("/Users/🚧🚧alice/Documents", "🍎📄🚧🚧🚧🚧alice"),
+            ("/Users/🚧/Documents/rust", "🍎📄🦀🚧🚧"),
+            ("/tmp/🚧🦀", "／tmp／🚧🚧🦀🦀"),
+        ];
+
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(PathBuf::from(path)));
+        }
+
+        assert_eq!(Codec::builder().register_dir('📄', "Projects").build().unwrap_err(), Error::InvalidConfig("icon 📄 of the registered dir \"Projects\" is already in use".into()));
+        assert_eq!(Codec::builder().register_dir('🚧', "a").register_dir('🚧', "b").build().unwrap_err(), Error::InvalidConfig("icon 🚧 of the registered dir \"b\" is already in use".into()));
+        assert_eq!(Codec::builder().register_dir('🚧', "a/b").build().unwrap_err(), Error::InvalidConfig("registered dir \"a/b\" must be a single path component".into()));
+        assert_eq!(Codec::builder().register_dir('x', "a").build().unwrap_err(), Error::InvalidConfig("icon 'x' of the registered dir \"a\" isn't an emoji or symbol".into()));
+        assert!(Codec::builder().register_dir('\u{1FB00}', "a").build().is_err());
+        assert!(Codec::builder().register_dir('☕', "a").build().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn parse_error() {
+        assert_eq!(to_path("🍎invalid"), Err(Error::ParseError(nom::error::Error { input: "invalid".into(), code: ErrorKind::Char })));
+        assert_eq!(to_path("💠📦alice"), Err(Error::ParseError(nom::error::Error { input: "📦alice".into(), code: ErrorKind::Char })));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn parse_error_in_unix() {
+        use std::os::unix::ffi::OsStringExt;
+        assert_eq!(to_path(OsString::from_vec(vec![0xc3u8, 0x28u8])), Err(Error::CouldntEncodeToUtf8(OsString::from_vec(vec![0xc3u8, 0x28u8]))));
+        assert_eq!(to_filename(PathBuf::from(OsString::from_vec(vec![0xc3u8, 0x28u8]))), Err(Error::CouldntEncodeToUtf8(OsString::from_vec(vec![0xc3u8, 0x28u8]))));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn shared_codec() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let codec = Codec::builder().register_dir('🚧', "Projects").decode_cache(8).build().unwrap();
+        assert_send_sync(&codec);
+        let clone = codec.clone();
+        assert!(Arc::ptr_eq(&codec.escaper, &clone.escaper));
+        assert!(Arc::ptr_eq(&codec.dirs, &clone.dirs));
+
+        let handles: Vec<_> = (0..4).map(|n| {
+            let codec = codec.clone();
+            std::thread::spawn(move || codec.to_path(format!("🍎📄🚧alice／{}", n)).unwrap())
+        }).collect();
+        for (n, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), PathBuf::from(format!("/Users/alice/Documents/Projects/{}", n)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn link_notation() {
+        let codec = Codec::builder().link_notation(true).build().unwrap();
+        let pairs = [
+            ("/home/alice/link -> /tmp/x", "🐧🏠alice／link🔀／tmp／x"),
+            ("C:\\Users\\bob\\Desktop\\app -> D:\\apps\\app", "💠🔝bob＼app🔀💠🥞D＼apps＼app"),
+            ("x -> y -> z", "x🔀y -＞ z"),
+            ("a🔀b -> c", "a🔁b🔀c"),
+            (" -> ", "🔀"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+        }
+        assert_eq!(to_filename("/home/alice/link -> /tmp/x"), Ok("🐧🏠alice／link -＞ ／tmp／x".into()));
+        assert_eq!(to_path("a🔀b"), Ok("a🔀b".into()));
+
+        assert_eq!(codec.to_path_and_counter("a🔀b🔢2"), Ok(("a -> b".into(), Some(2))));
+        assert_eq!(codec.to_path("a🔢2🔀b"), Err(Error::ParseError(nom::error::Error { input: "🔢2🔀b".into(), code: ErrorKind::Eof })));
+        assert_eq!(codec.to_path("a🔀🍎invalid"), Err(Error::ParseError(nom::error::Error { input: "invalid".into(), code: ErrorKind::Char })));
+
+        let filename = "🐧🏠alice🔀／x";
+        let kinds: Vec<_> = codec.parse(filename).unwrap().segments.into_iter().map(|segment| (segment.kind, &filename[segment.span])).collect();
+        assert_eq!(kinds, vec![
+            (SegmentKind::PlatformIcon(PlatformKind::Linux), "🐧"),
+            (SegmentKind::CommonDirIcon(CommonDir::Home), "🏠"),
+            (SegmentKind::UserToken("alice".into()), "alice"),
+            (SegmentKind::LinkArrow, "🔀"),
+            (SegmentKind::EscapedChar('/'), "／"),
+            (SegmentKind::Literal, "x"),
+        ]);
+    }
+
+    #[test]
+    fn compact_escapes() {
+        let codec = Codec::builder().compact_escapes(true).build().unwrap();
+        let pairs = [
+            ("／／／／／／", "〔FF0F*6〕"),
+            ("a／b", "a／／b"),
+            ("／／", "／／／／"),
+            ("／／／", "〔FF0F*3〕"),
+            ("🍏🍏🍏", "〔1F34F*3〕"),
+            ("〔4F〕", "〔〔4F〕"),
+            ("a/b", "a／b"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename_from_str(path), filename);
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+            assert_eq!(to_path(filename), Ok(path.into()));
+        }
+        let run = "〇".repeat(u16::MAX as usize + 2);
+        assert_eq!(codec.to_filename_from_str(&run), format!("〔3007*{}〕〇〇〇〇", u16::MAX));
+        assert_eq!(codec.to_path(codec.to_filename_from_str(&run)), Ok(run.into()));
+
+        // unlike 0.1, which kept them as they are
+        assert_eq!(to_filename_from_str("〔4F〕"), "〔〔4F〕");
+        assert_eq!(to_path("〔4F〕x〔4F*2〕"), Ok("OxOO".into()));
+        assert_eq!(to_path("〔zz〕〔4F*0〕〔110000〕〔4F*70000〕"), Ok("〔zz〕〔4F*0〕〔110000〕〔4F*70000〕".into()));
+        assert_eq!(parse("a〔4F*2〕").unwrap().segments[1], Segment { kind: SegmentKind::CompactEscape('O', 2), span: 1..11 });
+    }
+
+    #[test]
+    fn basenames() {
+        let pairs = [
+            ("file.txt", "file.txt"),
+            ("a/b:c.txt", "a／b：c.txt"),
+            ("🍎🏠alice", "🍏🏠alice"),
+            ("CON", "CO〔4E〕"),
+            ("nul.tar.gz", "nu〔6C〕.tar.gz"),
+            ("Com1 .txt", "Com〔31〕 .txt"),
+            ("LPT9", "LPT〔39〕"),
+            ("file.", "file〔2E〕"),
+            ("file. ", "file.〔20〕"),
+            ("COM10", "COM10"),
+            ("CONSOLE", "CONSOLE"),
+            ("a\u{1}b\u{7f}", "a〔1〕b〔7F〕"),
+            ("\u{1f}\t.", "〔1F〕⏩〔2E〕"),
+            ("", ""),
+        ];
+        for (name, filename) in pairs {
+            assert_eq!(encode_basename(name), Ok(filename.into()));
+            assert_eq!(to_path(filename), Ok(name.into()));
+        }
+        assert_eq!(Codec::builder().spaces(SpaceReplacement::OpenBox).build().unwrap().encode_basename_from_str("a b "), "a␣b〔20〕");
+    }
+
+    #[test]
+    fn joined_names() {
+        let dir = to_filename_from_str("/tmp/a🔗b📎c");
+        let file = to_filename_from_str("file🔢1");
+        assert_eq!(dir, "／tmp／a📎b📎📎c");
+        let joined = join_encoded(&[&dir, &file]).unwrap();
+        assert_eq!(joined, "／tmp／a📎b📎📎c🔗file🔣1");
+        let names = split_encoded(&joined);
+        assert_eq!(names, vec![dir, file]);
+        assert_eq!(to_path(&names[0]), Ok("/tmp/a🔗b📎c".into()));
+        assert_eq!(to_path(&names[1]), Ok("file🔢1".into()));
+
+        assert_eq!(split_encoded(join_encoded(&["", "a", ""]).unwrap()), vec!["", "a", ""]);
+        assert_eq!(join_encoded(&["x📎🔗", "y"]), Err(Error::ParseError(nom::error::Error { input: "🔗".into(), code: ErrorKind::Char })));
+        assert_eq!(join_encoded(&[] as &[&str]), Ok("".into()));
+    }
+
+    // an escaping only codec encodes and decodes alike with and without the platform feature
+    #[test]
+    fn escaping_only() {
+        let codec = Codec::builder().escaping_only(true).register_dir('🚧', "Projects").build().unwrap();
+        let restored = Codec::from_config_string(codec.to_config_string()).unwrap();
+        let pairs = [
+            ("/Users/alice/Documents/my:file.txt", "／Users／alice／Documents／my：file.txt"),
+            ("C:\\x\\🍎", "C：＼x＼🍏"),
+            ("/media/usb/x", "／media／usb／x"),
+            ("/home/alice/Projects/🚧", "／home／alice／Projects／🚧🚧"),
+            ("🧾a🔀b", "🧾a🔁b"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+            assert_eq!(restored.to_filename(path), Ok(filename.into()));
+        }
+        assert_eq!(codec.to_path("🍎📄alice／file.txt"), Ok("🍎📄alice/file.txt".into()));
+        assert_eq!(codec.parse("🍎📄alice").unwrap().segments, vec![Segment { kind: SegmentKind::Literal, span: 0..13 }]);
+        #[cfg(feature = "platform")]
+        assert_eq!(codec.detect_platform_of_filename("🍎📄alice"), None);
+
+        // without the platform feature, it's the default codec
+        #[cfg(not(feature = "platform"))]
+        for (path, filename) in pairs.into_iter().filter(|(path, _)| !path.contains('🚧')) {
+            assert_eq!(to_filename(path), Ok(filename.into()));
+            assert_eq!(to_path(filename), Ok(path.into()));
+        }
+        #[cfg(not(feature = "platform"))]
+        assert_eq!(Codec::builder().escaping_only(false).build().unwrap().to_config_string(), codec.to_config_string().replace("dirs=🚧Projects", "dirs="));
     }
 
     #[test]
@@ -582,14 +2451,239 @@ mod tests {
             Error::from(Err::<nom::error::Error<&str>>::Failure(nom::error::Error { input: "error", code: ErrorKind::Fail })),
             Error::ParseError(nom::error::Error { input: "error".into(), code: ErrorKind::Fail }),
         );
-        assert_eq!(
-            Platform::parse_fail("error"),
-            Err(Err::Error(nom::error::Error { input: "error", code: ErrorKind::Fail })),
-        );
         assert_eq!(
             format!("{:?}", Error::ParseError(nom::error::Error { input: "error".into(), code: ErrorKind::Fail })),
             "ParseError(Error { input: \"error\", code: Fail })".to_string(),
         );
     }
-}
 
+    #[test]
+    #[cfg(feature = "platform")]
+    fn selective_compression() {
+        let codec = Codec::builder()
+            .compress_dir(CommonDir::Downloads, false)
+            .compress_dir(CommonDir::Drive, false)
+            .drive_letter_case(DriveLetterCase::Uppercase)
+            .build().unwrap();
+        let cases = [
+            ("/Users/alice/Downloads/x", "🍎🏠alice／Downloads／x"),
+            ("/Users/alice/Documents/x", "🍎📄alice／x"),
+            ("/Volumes/USB/x", "／Volumes／USB／x"),
+            ("/media/sdcard/x", "／media／sdcard／x"),
+            ("d:\\x", "d：＼x"),
+            ("c:\\Users\\alice\\Downloads", "💠🔡🏠alice＼Downloads"),
+        ];
+        for (path, filename) in cases {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()), "{}", path);
+            assert_eq!(codec.to_path(filename), Ok(path.into()), "{}", path);
+        }
+        assert_eq!(codec.to_path("🍎⏬alice／x"), Ok("/Users/alice/Downloads/x".into()));
+
+        let codec = Codec::builder().compress_dir(CommonDir::Home, false).build().unwrap();
+        assert_eq!(codec.to_filename("/home/alice/x"), Ok("／home／alice／x".into()));
+        assert_eq!(codec.to_filename("/home/alice/Desktop/x"), Ok("🐧🔝alice／x".into()));
+
+        let codec = Codec::builder().compress_dir(CommonDir::Home, false).compress_dir(CommonDir::Home, true).build().unwrap();
+        assert_eq!(codec.to_filename("/home/alice/x"), Ok("🐧🏠alice／x".into()));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn rehomed_paths() {
+        let cases = [
+            ("🍎📄alice／x", "/home/bob/Documents/x"),
+            ("🍎🔡🏠alice／a／b", "/home/bob/a/b"),
+            ("💠⏬alice＼a＼b.txt", "/home/bob/Downloads/a/b.txt"),
+            ("💠🏠alice", "/home/bob"),
+            ("🐧💾alice／x📂", "/home/bob/.local/share/x"),
+            ("🍎🥞USB／x", "/Volumes/USB/x"),
+            ("／tmp／x", "/tmp/x"),
+        ];
+        for (filename, path) in cases {
+            assert_eq!(to_path_rehomed(filename, "/home/bob"), Ok(PathBuf::from(path)), "{}", filename);
+        }
+        assert_eq!(to_path_rehomed("💠🏠alice＼a＼b", "/home/bob").map(|path| path.components().count()), Ok(5));
+
+        let codec = Codec::builder().link_notation(true).register_dir('🚧', "Projects").build().unwrap();
+        assert_eq!(codec.to_path_rehomed("🐧🏠🚧alice／x🔀🍎🏠carol／y", "/home/bob"), Ok("/home/bob/Projects/x -> /home/bob/y".into()));
+        assert!(codec.to_path_rehomed("🍎invalid", "/home/bob").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn env_var_paths() {
+        let cases = [
+            ("💠📄alice＼x.txt", "%USERPROFILE%\\Documents\\x.txt"),
+            ("💠🏠alice", "%USERPROFILE%"),
+            ("💠💾alice＼Temp＼a.tmp", "%TEMP%\\a.tmp"),
+            ("💠💾alice＼Temporary", "%LOCALAPPDATA%\\Temporary"),
+            ("💠🏠alice＼appdata＼roaming＼App", "%APPDATA%\\App"),
+            ("💠🏠alice＼AppData＼Roaming2", "%USERPROFILE%\\AppData\\Roaming2"),
+            ("💠🥞D＼x", "D:\\x"),
+            ("🐧📄alice／x", "/home/alice/Documents/x"),
+            ("／tmp／x", "/tmp/x"),
+        ];
+        for (filename, path) in cases {
+            assert_eq!(to_path_with_env_vars(filename), Ok(PathBuf::from(path)), "{}", filename);
+        }
+
+        let codec = Codec::builder().link_notation(true).build().unwrap();
+        assert_eq!(codec.to_path_with_env_vars("💠🏠alice＼x🔀💠📄alice＼y"), Ok("%USERPROFILE%\\x -> %USERPROFILE%\\Documents\\y".into()));
+        assert!(codec.to_path_with_env_vars("🍎invalid").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "localize")]
+    fn localized_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(to_path_localized("🍎📄alice／x"), Ok(home.join("Documents").join("x")));
+        assert_eq!(to_path_localized("／tmp／x"), Ok("/tmp/x".into()));
+    }
+
+    #[test]
+    fn decode_limits() {
+        let codec = Codec::builder().max_input_len(32).max_escape_expansion(100).build().unwrap();
+        assert_eq!(codec.to_path("／tmp／a〔41*99〕"), Ok(format!("/tmp/a{}", "A".repeat(99)).into()));
+        assert_eq!(codec.to_path("a〔41*50〕〔42*51〕"), Err(Error::LimitExceeded("the compact escapes expand to 101 chars, over the limit of 100".into())));
+        assert_eq!(codec.to_path(format!("／tmp／{}", "a".repeat(24))), Err(Error::LimitExceeded("the filename is 33 bytes, over the limit of 32".into())));
+        // a doubled `〔` is a literal, not the start of a compact escape
+        assert_eq!(codec.to_path("〔〔41*999〕"), Ok("〔41*999〕".into()));
+        assert!(codec.parse("a〔41*50〕〔42*51〕").is_err());
+        assert_eq!(Codec::new().to_path("〔41*999〕").map(|path| path.as_os_str().len()), Ok(999));
+    }
+
+    #[test]
+    fn path_components() {
+        assert_eq!(to_filename_components(Path::new("/tmp//a/./b/").components()), Ok("／tmp／a／b".into()));
+        assert_eq!(to_filename_components(Path::new("./a").components()), Ok(".／a".into()));
+        assert_eq!(to_filename_components(Path::new("").components()), Ok("".into()));
+        #[cfg(feature = "platform")]
+        assert_eq!(to_filename_components(Path::new("/home//alice/x").components()), Ok("🐧🏠alice／x".into()));
+        #[cfg(all(windows, feature = "platform"))]
+        {
+            assert_eq!(to_filename_components(Path::new("C:/Users/alice/x").components()), Ok("💠🏠alice＼x".into()));
+            assert_eq!(to_filename_components(Path::new("\\\\?\\C:\\Users\\alice\\x").components()), Ok("💠🏠alice＼x".into()));
+            assert_eq!(to_filename_components(Path::new("\\\\?\\UNC\\server\\share\\x").components()), Ok(to_filename_from_str("\\\\server\\share\\x")));
+            assert_eq!(to_filename_components(Path::new("\\\\.\\pipe\\x").components()), Ok(to_filename_from_str("\\\\.\\pipe\\x")));
+            assert_eq!(to_filename_components(Path::new("C:x/y").components()), Ok(to_filename_from_str("C:x\\y")));
+        }
+    }
+
+    #[test]
+    fn borrowed_paths() {
+        assert!(matches!(to_path_cow("report.txt"), Ok(Cow::Borrowed(path)) if path == Path::new("report.txt")));
+        assert!(matches!(to_path_cow(OsStr::new("a b.txt")), Ok(Cow::Borrowed(_))));
+        assert!(matches!(to_path_cow("／tmp／a"), Ok(Cow::Owned(path)) if path == Path::new("/tmp/a")));
+        assert!(matches!(to_path_cow("a📂"), Ok(Cow::Owned(path)) if path == Path::new("a/")));
+        assert!(matches!(to_path_cow("a〔41〕"), Ok(Cow::Owned(path)) if path == Path::new("aA")));
+
+        let codec = Codec::builder().spaces(SpaceReplacement::OpenBox).max_input_len(5).build().unwrap();
+        assert!(matches!(codec.to_path_cow("a b"), Ok(Cow::Owned(path)) if path == Path::new("a b")));
+        assert!(matches!(codec.to_path_cow("a␣b"), Ok(Cow::Owned(path)) if path == Path::new("a b")));
+        assert!(matches!(codec.to_path_cow("abcdef"), Err(Error::LimitExceeded(_))));
+
+        for name in ["report.txt", "a b", "／tmp／a", "🍎📄alice／x", "🐧invalid", "x🔢2", "🚧"] {
+            assert_eq!(to_path_cow(name).map(Cow::into_owned), to_path(name), "{}", name);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn unknown_icons() {
+        let codec = |policy| Codec::builder().unknown_icons(policy).register_dir('🚧', "Projects").build().unwrap();
+        let (pass, error, strip) = (codec(UnknownIconPolicy::PassThrough), codec(UnknownIconPolicy::Error), codec(UnknownIconPolicy::Strip));
+        assert_eq!(pass.to_path("🦀／tmp"), Ok("🦀/tmp".into()));
+        assert_eq!(error.to_path("🦀／tmp"), Err(Error::ParseError(nom::error::Error { input: "🦀／tmp".into(), code: nom::error::ErrorKind::Char })));
+        assert_eq!(strip.to_path("🦀／tmp"), Ok("/tmp".into()));
+        assert_eq!(strip.to_path("☀️🦀x"), Ok("x".into()));
+        assert_eq!(strip.to_path("x🦀"), Ok("x🦀".into()));
+        assert!(matches!(strip.to_path_cow("🦀x"), Ok(Cow::Owned(path)) if path == Path::new("x")));
+        assert!(error.to_path_cow("🦀x").is_err());
+
+        // the icons and escapes of the codec, and chars that don't look like icons
+        for name in ["🍏x", "⏩x", "📄x", "🚧x", "📂", "日本", "🍎📄alice／x"] {
+            assert_eq!(error.to_path(name), pass.to_path(name), "{}", name);
+            assert_eq!(strip.to_path(name), pass.to_path(name), "{}", name);
+        }
+    }
+
+    // the chars the codecs treat specially, mixed into the inputs of the no panic tests
+    const NO_PANIC_ALPHABET: &str = "a:/\\ .🍎🐧💠🍏🐤🚪🏠📄🥞🔡🔢🔣📂📁🔗🔀〔〕*=1F／＼：🚧🧾📜Я";
+
+    fn no_panic_codecs() -> [Codec; 3] {
+        let builder = Codec::builder()
+            .trailing_separator(TrailingSeparator::Mark)
+            .collapse_separators(true)
+            .link_notation(true)
+            .compact_escapes(true)
+            .transliterate(CyrillicToLatin)
+            .header(true)
+            .register_dir('🚧', "Projects");
+        #[cfg(feature = "platform")]
+        let builder = builder.drive_letter_case(DriveLetterCase::Uppercase).intern_tokens(2);
+        [Codec::new(), builder.build().unwrap(), Codec::builder().escaping_only(true).build().unwrap()]
+    }
+
+    // the functions the crate docs promise don't panic, on a single input
+    fn exercise(codec: &Codec, name: &str) {
+        let _ = codec.to_path_from_str(name);
+        let _ = codec.to_path_cow(name);
+        let _ = codec.decode_auto(name);
+        let _ = codec.to_filename_from_str(name);
+        let _ = codec.to_filename_components(Path::new(name).components());
+        let _ = codec.encode_basename_from_str(name);
+        let _ = codec.encoded_len(name);
+        let _ = codec.parse(name);
+        for anomaly in codec.decode_unchecked_report(name).anomalies {
+            assert!(name.get(anomaly.span.clone()).is_some(), "{:?} {:?}", name, anomaly);
+        }
+        for fix in codec.suggest_fix(name) {
+            let _ = fix.apply(name);
+            let _ = fix.apply("");
+        }
+        #[cfg(feature = "platform")]
+        {
+            let _ = codec.detect_platform_of_filename(name);
+            let _ = codec.to_path_rehomed(name, "/home/bob");
+            let _ = codec.to_path_with_env_vars(name);
+        }
+        let _ = Codec::from_config_string(name);
+        let _ = Codec::from_config_string(format!("v1;{}", name));
+        let _ = split_encoded(name);
+        let _ = join_encoded(&[name, name]);
+    }
+
+    // every string of up to 3 chars mixing icons, escapes and separators goes through the public functions
+    #[test]
+    fn no_panics() {
+        let codecs = no_panic_codecs();
+        let alphabet: Vec<char> = NO_PANIC_ALPHABET.chars().collect();
+
+        let mut names = vec![String::new()];
+        for len in 1..=3 {
+            let shorter: Vec<_> = names.iter().filter(|name| name.chars().count() == len - 1).cloned().collect();
+            for name in shorter {
+                names.extend(alphabet.iter().map(|c| format!("{}{}", name, c)));
+            }
+        }
+        for codec in &codecs {
+            for name in &names {
+                exercise(codec, name);
+            }
+            let _ = codec.decode_batch(&names);
+        }
+        assert_eq!(Fix { span: std::ops::Range { start: 2, end: 1 }, replacement: Some("x".into()), message: String::new() }.apply("abc"), None);
+        assert_eq!(Fix { span: 0..1, replacement: Some("x".into()), message: String::new() }.apply("🍎"), None);
+    }
+
+    proptest::proptest! {
+        // longer strings of any chars, biased to the ones the codecs treat specially
+        #[test]
+        fn no_panics_on_any_string(chars in proptest::collection::vec(proptest::prop_oneof![proptest::char::any(), proptest::sample::select(NO_PANIC_ALPHABET.chars().collect::<Vec<_>>())], 0..40)) {
+            let name: String = chars.into_iter().collect();
+            for codec in &no_panic_codecs() {
+                exercise(codec, &name);
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io,
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{
+    event::ModifyKind,
+    Event,
+    EventKind,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+};
+
+use super::*;
+
+/// Keeps a flat directory of copies of the files in a source tree, each named by its encoded source path.
+///
+/// [`Mirror::new`] copies the current files and starts watching the source. The changes since then are applied
+/// to the target by [`Mirror::process_pending`], [`Mirror::process_timeout`] or [`Mirror::run`], including
+/// files and directories that are created, modified, renamed and deleted. [`Mirror::sync`] repairs the target
+/// from scratch, which is also done when the watcher reports it lost events.
+///
+/// Symlinks to files are copied as the files they point to, while symlinks to directories are skipped, so a link
+/// to an ancestor doesn't loop. Entries of the target that don't decode into the source tree are left alone.
+pub struct Mirror {
+    codec: Codec,
+    source: PathBuf,
+    target: PathBuf,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    // dropping it stops the watching
+    _watcher: RecommendedWatcher,
+}
+
+impl Mirror {
+    /// Mirrors `source` into `target`, which is created if missing and must not be inside `source`.
+    pub fn new(codec: Codec, source: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<Self, Error> {
+        let source = fs::canonicalize(source)?;
+        let target = canonicalize_missing(target.as_ref())?;
+        if target.starts_with(&source) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the target dir is inside the source dir").into());
+        }
+        fs::create_dir_all(&target)?;
+
+        // watching before the first sync, so no change falls in between
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&source, RecursiveMode::Recursive)?;
+        let mirror = Self { codec, source, target, events, _watcher: watcher };
+        mirror.sync()?;
+        Ok(mirror)
+    }
+
+    /// The canonicalized source dir, which the encoded names are the paths under.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    pub fn target(&self) -> &Path {
+        &self.target
+    }
+
+    /// Copies every file of the source and removes the copies whose originals are gone.
+    pub fn sync(&self) -> Result<(), Error> {
+        self.reconcile(&self.source)
+    }
+
+    /// Applies the changes reported so far without blocking, and returns how many events were handled.
+    pub fn process_pending(&self) -> Result<usize, Error> {
+        let mut count = 0;
+        while let Ok(event) = self.events.try_recv() {
+            self.handle(event?)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Waits up to `timeout` for a change, then applies it and the others reported so far.
+    pub fn process_timeout(&self, timeout: Duration) -> Result<usize, Error> {
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => {
+                self.handle(event?)?;
+                Ok(1 + self.process_pending()?)
+            },
+            Err(_) => Ok(0),
+        }
+    }
+
+    /// Applies the changes as they come, until the watcher fails.
+    pub fn run(&self) -> Result<(), Error> {
+        while let Ok(event) = self.events.recv() {
+            self.handle(event?)?;
+        }
+        Ok(())
+    }
+
+    fn handle(&self, event: Event) -> Result<(), Error> {
+        if event.need_rescan() {
+            return self.sync();
+        }
+        if matches!(event.kind, EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))) {
+            return Ok(());
+        }
+        // a rename is reported with the old path, the new path, or both, so each path is looked at as it is now
+        for path in &event.paths {
+            if path.starts_with(&self.source) {
+                self.reconcile(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    // copies the files at or under `path`, walking the real dirs without following symlinks to dirs, and removes
+    // the copies of the others
+    fn reconcile(&self, path: &Path) -> Result<(), Error> {
+        let mut files = HashSet::new();
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(path) = pending.pop() {
+            // it's removed since, which comes as another event
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                match fs::read_dir(&path) {
+                    Ok(entries) => {
+                        for entry in entries {
+                            pending.push(entry?.path());
+                        }
+                    },
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    Err(err) => return Err(err.into()),
+                }
+            } else if metadata.is_file() || (metadata.is_symlink() && path.is_file()) {
+                self.copy(&path)?;
+                files.insert(path);
+            }
+        }
+        self.remove_stale(path, &files)
+    }
+
+    fn copy(&self, path: &Path) -> Result<(), Error> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            // it's removed since, which comes as another event
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        self.codec.write_encoded(&self.target, path, bytes)?;
+        Ok(())
+    }
+
+    // removes the copies of the files at or under `path` other than `files`, scanning the target once
+    fn remove_stale(&self, path: &Path, files: &HashSet<PathBuf>) -> Result<(), Error> {
+        for entry in fs::read_dir(&self.target)? {
+            let entry = entry?;
+            let Ok(original) = self.codec.to_path(entry.file_name()) else {
+                continue;
+            };
+            if original.starts_with(path) && !files.contains(&original) {
+                match fs::remove_file(entry.path()) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// canonicalizes the deepest existing ancestor of `path` and appends the missing components to it, so a dir can be
+// checked before it's created
+fn canonicalize_missing(path: &Path) -> io::Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        missing.push(name);
+        existing = parent;
+    }
+    let existing = if existing.as_os_str().is_empty() { Path::new(".") } else { existing };
+    let mut canonical = fs::canonicalize(existing)?;
+    canonical.extend(missing.iter().rev());
+    Ok(canonical)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    fn names(mirror: &Mirror) -> Vec<String> {
+        let mut names: Vec<_> = fs::read_dir(mirror.target()).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+        names.sort();
+        names
+    }
+
+    // events come asynchronously, so they are processed until the target is as expected
+    fn wait_for(mirror: &Mirror, expected: &[String]) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while names(mirror) != expected && Instant::now() < deadline {
+            mirror.process_timeout(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(names(mirror), expected);
+    }
+
+    #[test]
+    fn mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let source = dir.join("source");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub").join("a.txt"), "a").unwrap();
+
+        let mirror = Mirror::new(Codec::new(), &source, dir.join("target")).unwrap();
+        let name = |path: &str| to_filename(mirror.source().join(path)).unwrap();
+        assert_eq!(names(&mirror), [name("sub/a.txt")]);
+        assert_eq!(fs::read(mirror.target().join(name("sub/a.txt"))).unwrap(), b"a");
+
+        fs::write(source.join("b.txt"), "b").unwrap();
+        wait_for(&mirror, &[name("b.txt"), name("sub/a.txt")]);
+
+        fs::write(source.join("b.txt"), "bb").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while fs::read(mirror.target().join(name("b.txt"))).unwrap() != b"bb" && Instant::now() < deadline {
+            mirror.process_timeout(Duration::from_millis(100)).unwrap();
+        }
+        assert_eq!(fs::read(mirror.target().join(name("b.txt"))).unwrap(), b"bb");
+
+        fs::rename(source.join("sub"), source.join("moved")).unwrap();
+        wait_for(&mirror, &[name("b.txt"), name("moved/a.txt")]);
+
+        fs::remove_file(source.join("b.txt")).unwrap();
+        wait_for(&mirror, &[name("moved/a.txt")]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let source = dir.join("source");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("sub").join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink(&source, source.join("sub").join("loop")).unwrap();
+        std::os::unix::fs::symlink(source.join("sub").join("a.txt"), source.join("b.txt")).unwrap();
+
+        let mirror = Mirror::new(Codec::new(), &source, dir.join("target")).unwrap();
+        let name = |path: &str| to_filename(mirror.source().join(path)).unwrap();
+        assert_eq!(names(&mirror), [name("b.txt"), name("sub/a.txt")]);
+        assert_eq!(fs::read(mirror.target().join(name("b.txt"))).unwrap(), b"a");
+    }
+
+    #[test]
+    fn target_inside_source() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(Mirror::new(Codec::new(), dir.path(), dir.path().join("target")), Err(Error::Io(_))));
+        assert!(matches!(Mirror::new(Codec::new(), dir.path(), dir.path().join("a/b")), Err(Error::Io(_))));
+        assert!(!dir.path().join("target").exists());
+        assert!(!dir.path().join("a").exists());
+    }
+}
@@ -0,0 +1,180 @@
+use super::*;
+
+// the filesystem types of network shares, whose sources name the same share on every machine
+const NETWORK_FS_TYPES: [&str; 11] = ["nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs", "davfs", "fuse.sshfs"];
+
+// the separator of the optional fields and the filesystem fields of a line of `/proc/self/mountinfo`
+const MOUNTINFO_SEP: &str = "-";
+
+// a mount of a table, where `root` is the dir of the filesystem mounted, known only from `/proc/self/mountinfo`
+struct Mount {
+    source: String,
+    mount_point: String,
+    fs_type: String,
+    root: Option<String>,
+    subvol: Option<String>,
+}
+
+impl Mount {
+    // `36 25 8:1 /data /srv/data rw,relatime shared:1 - ext4 /dev/sda1 rw`
+    fn parse_mountinfo(line: &str) -> Option<Self> {
+        let fields: Vec<_> = line.split_whitespace().map(unescape_field).collect();
+        let sep = fields.iter().skip(6).position(|field| field == MOUNTINFO_SEP)? + 6;
+        let (root, mount_point) = (fields.get(3)?, fields.get(4)?);
+        let (fs_type, source, options) = (fields.get(sep + 1)?, fields.get(sep + 2)?, fields.get(sep + 3));
+        let subvol = options.and_then(|options| options.split(',').find_map(|option| option.strip_prefix("subvol=")));
+        Some(Self { source: source.clone(), mount_point: mount_point.clone(), fs_type: fs_type.clone(), root: Some(root.clone()), subvol: subvol.map(String::from) })
+    }
+
+    // `/dev/sda1 /srv/data ext4 rw,relatime 0 0`
+    fn parse_mounts(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace().map(unescape_field);
+        let (source, mount_point, fs_type) = (fields.next()?, fields.next()?, fields.next()?);
+        Some(Self { source, mount_point, fs_type, root: None, subvol: None })
+    }
+
+    // the name of what's mounted, the same on every machine mounting it: the source of a network share, or the
+    // device and dir of a bind mount, which is one of a dir other than the root of the filesystem or of its btrfs
+    // subvolume
+    fn name(&self) -> Option<String> {
+        if NETWORK_FS_TYPES.contains(&self.fs_type.as_str()) {
+            return Some(self.source.clone());
+        }
+        let root = self.root.as_deref()?;
+        (root != "/" && self.subvol.as_deref() != Some(root)).then(|| format!("{}:{}", self.source, root))
+    }
+}
+
+// the mount points of a table in the format of `/proc/self/mountinfo` or `/proc/mounts` that are compressed like
+// a dir under `/media`, named with a NUL and their percent-escaped names, so they never spell a volume under it
+pub(crate) fn relocations(table: &str) -> Vec<Relocation> {
+    let mut relocations = Vec::new();
+    for line in table.lines() {
+        let Some(mount) = Mount::parse_mountinfo(line).or_else(|| Mount::parse_mounts(line)) else {
+            continue;
+        };
+        if mount.mount_point == "/" || mount.mount_point.starts_with("/media/") {
+            continue;
+        }
+        let Some(name) = mount.name() else {
+            continue;
+        };
+        relocations.push(Relocation { canonical: format!("/media/\0{}", source_token(&name)), actual: mount.mount_point, mount: true });
+    }
+    relocations
+}
+
+// the name as a single path component, like `nas:%2Fexport` for `nas:/export`
+fn source_token(name: &str) -> String {
+    name.replace('%', "%25").replace(POSIX_SEP, "%2F")
+}
+
+// decodes the octal escapes of spaces, tabs, newlines and backslashes in the fields
+fn unescape_field(field: &str) -> String {
+    let mut r = String::new();
+    let mut rest = field;
+    while let Some(n) = rest.find('\\') {
+        r.push_str(rest.get(..n).unwrap_or_default());
+        match rest.get(n + 1..n + 4).and_then(|octal| u8::from_str_radix(octal, 8).ok()) {
+            Some(byte) => {
+                r.push(byte as char);
+                rest = rest.get(n + 4..).unwrap_or_default();
+            },
+            None => {
+                r.push('\\');
+                rest = rest.get(n + 1..).unwrap_or_default();
+            },
+        }
+    }
+    r.push_str(rest);
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "\
+        sysfs /sys sysfs rw,nosuid 0 0\n\
+        /dev/sda1 / ext4 rw,relatime 0 0\n\
+        nas:/export/music /mnt/music nfs4 rw,vers=4.2 0 0\n\
+        //nas/my%share /mnt/my\\040share cifs rw 0 0\n\
+        /dev/sda1 /srv/data ext4 rw,relatime 0 0\n\
+        /dev/sdb1 /media/usb vfat rw 0 0\n\
+        nas:/export/home /home/alice/nas nfs rw 0 0\n\
+        broken\n";
+
+    // btrfs subvolumes on `/` and `/home`, a bind mount of a dir of one, and one of a dir of an ext4 device
+    const MOUNTINFO: &str = "\
+        22 1 0:21 /@ / rw,relatime shared:1 - btrfs /dev/sda2 rw,subvolid=256,subvol=/@\n\
+        23 22 0:21 /@home /home rw,relatime shared:2 - btrfs /dev/sda2 rw,subvolid=257,subvol=/@home\n\
+        24 22 0:21 /@home/alice/data /srv/data rw,relatime shared:2 - btrfs /dev/sda2 rw,subvolid=257,subvol=/@home\n\
+        25 22 8:17 / /boot rw,relatime shared:3 - ext4 /dev/sdb1 rw\n\
+        26 22 8:17 /exports /srv/exports rw,relatime shared:3 - ext4 /dev/sdb1 rw\n\
+        27 22 8:17 /exports/bob /home/bob/exports rw,relatime shared:3 - ext4 /dev/sdb1 rw\n\
+        28 22 0:40 / /mnt/music rw,relatime shared:4 - nfs4 nas:/export/music rw,vers=4.2\n\
+        29 22 8:33 / /media/usb rw,relatime shared:5 - vfat /dev/sdc1 rw\n";
+
+    #[test]
+    fn mount_table() {
+        let codec = Codec::builder().mount_table(TABLE).build().unwrap();
+        let pairs = [
+            ("/mnt/music/a.mp3", "🐧🥞〇nas：%2Fexport%2Fmusic／a.mp3"),
+            ("/mnt/music", "🐧🥞〇nas：%2Fexport%2Fmusic"),
+            ("/mnt/my share/x", "🐧🥞〇%2F%2Fnas%2Fmy%25share／x"),
+            // bind mounts aren't told from other mounts of a device in this format
+            ("/srv/data/x", "／srv／data／x"),
+            ("/media/usb/x", "🐧🥞usb／x"),
+            // the common root dir of a path is kept where something is mounted in it
+            ("/home/alice/nas/x", "🐧🏠alice／nas／x"),
+            ("/mnt/musical", "／mnt／musical"),
+            ("/sys/x", "／sys／x"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+        }
+        // the name stays readable where the share isn't mounted, with a NUL no volume under `/media` has
+        assert_eq!(to_path("🐧🥞〇nas：%2Fexport%2Fmusic／a.mp3"), Ok("/media/\0nas:%2Fexport%2Fmusic/a.mp3".into()));
+        assert_eq!(codec.to_filename("/media/nas:%2Fexport%2Fmusic/a.mp3"), Ok("🐧🥞nas：%2Fexport%2Fmusic／a.mp3".into()));
+        assert_eq!(codec.to_path("🐧🥞nas：%2Fexport%2Fmusic／a.mp3"), Ok("/media/nas:%2Fexport%2Fmusic/a.mp3".into()));
+    }
+
+    #[test]
+    fn machine_dependent() {
+        let codec = Codec::builder().mount_table(TABLE).build().unwrap();
+        assert_ne!(codec.minimal_config_string(), Codec::new().minimal_config_string());
+        assert_eq!(codec.minimal_config_string(), "v1;local=mounts");
+        assert_eq!(Codec::from_config_string(codec.to_config_string()).err(), Some(Error::InvalidConfig("the config depends on the mounts of the machine it was made on".into())));
+        let headered = Codec::builder().header(true).mount_table(TABLE).build();
+        assert_eq!(headered.err(), Some(Error::InvalidConfig("a header can't hold the mounts of this machine".into())));
+        // a table mounting nothing relocated changes nothing
+        assert!(Codec::builder().header(true).mount_table("/dev/sda1 / ext4 rw 0 0").build().is_ok());
+    }
+
+    #[test]
+    fn mountinfo() {
+        let codec = Codec::builder().mount_table(MOUNTINFO).build().unwrap();
+        let pairs = [
+            ("/home/alice/Documents/x", "🐧📄alice／x"),
+            ("/srv/data/x", "🐧🥞〇%2Fdev%2Fsda2：%2F@home%2Falice%2Fdata／x"),
+            ("/srv/exports/x", "🐧🥞〇%2Fdev%2Fsdb1：%2Fexports／x"),
+            ("/home/bob/exports/x", "🐧🏠bob／exports／x"),
+            ("/boot/x", "／boot／x"),
+            ("/mnt/music/a.mp3", "🐧🥞〇nas：%2Fexport%2Fmusic／a.mp3"),
+            ("/media/usb/x", "🐧🥞usb／x"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn local_mounts() {
+        let codec = Codec::builder().local_mounts().unwrap().build().unwrap();
+        // the pseudo filesystems are never compressed
+        assert_eq!(codec.to_filename("/proc/x"), Ok("／proc／x".into()));
+    }
+}
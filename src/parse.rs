@@ -0,0 +1,335 @@
+use std::ops::Range;
+
+use super::*;
+
+/// An encoded filename split into typed segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed {
+    pub segments: Vec<Segment>,
+}
+
+/// A part of an encoded filename and the byte range it spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// The OS icon at the start, like `🍎`.
+    PlatformIcon(PlatformKind),
+    /// `🔡`, telling the root of the original path was spelled in lowercase.
+    LowercaseRoot,
+    /// The icon of a common directory, like `🏠` or `🥞`.
+    CommonDirIcon(CommonDir),
+    /// The icon of a directory registered with [`CodecBuilder::register_dir`], with its name.
+    RegisteredDirIcon(String),
+    /// The user (or the volume for [`CommonDir::Drive`]) following the icons, decoded.
+    UserToken(String),
+    /// An escaped char, with the original char it stands for.
+    EscapedChar(char),
+    /// A compact escape like `〔FF0F*5〕`, with the original char and how many times it's repeated.
+    CompactEscape(char, u16),
+    /// A compact escape of [`CodecBuilder::transliterate`] like `〔Privet=41F,440,438,432,435,442〕`, with the
+    /// original chars.
+    Transliteration(String),
+    /// A run of chars kept as is.
+    Literal,
+    /// `📂`, standing for a trailing separator.
+    TrailingSeparator,
+    /// The counter suffix added by [`Codec::to_unique_filename_in`].
+    Counter(u32),
+    /// `🔀` between a link and its target, with [`CodecBuilder::link_notation`].
+    LinkArrow,
+    /// The header of [`CodecBuilder::header`] at the start, with the config string it holds.
+    Header(String),
+}
+
+impl Codec {
+    /// Splits an encoded `filename` into segments with their byte spans, e.g. for syntax highlighting.
+    ///
+    /// It fails in the same cases as [`Codec::to_path_from_str`].
+    pub fn parse(&self, filename: impl AsRef<str>) -> Result<Parsed, Error> {
+        let filename = filename.as_ref();
+        self.to_path_and_counter_from_str(filename)?;
+
+        let mut segments = Vec::new();
+        let rest = self.strip_header(filename)?;
+        let start = filename.len() - rest.len();
+        if start > 0 {
+            if let Some((config, _)) = config::split_header(filename)? {
+                segments.push(Segment { kind: SegmentKind::Header(config), span: 0..start });
+            }
+        }
+        match self.split_link(rest) {
+            Some((path, target)) => {
+                self.push_segments(path, start, &mut segments)?;
+                let start = start + path.len();
+                segments.push(Segment { kind: SegmentKind::LinkArrow, span: start..start + LINK_ICON.len_utf8() });
+                self.push_segments(target, start + LINK_ICON.len_utf8(), &mut segments)?;
+            },
+            None => self.push_segments(rest, start, &mut segments)?,
+        }
+
+        // merges adjacent literal chars into runs
+        let mut merged: Vec<Segment> = Vec::new();
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last) if last.kind == SegmentKind::Literal && segment.kind == SegmentKind::Literal => last.span.end = segment.span.end,
+                _ => merged.push(segment),
+            }
+        }
+        Ok(Parsed { segments: merged })
+    }
+
+    // pushes the segments of a name starting at `start` of the whole filename
+    fn push_segments(&self, filename: &str, start: usize, segments: &mut Vec<Segment>) -> Result<(), Error> {
+        let offset = |i: &str| start + filename.len() - i.len();
+        let mut push = |kind, from: &str, to: &str| {
+            segments.push(Segment { kind, span: offset(from)..offset(to) });
+        };
+
+        #[cfg(feature = "platform")]
+        let mut i = match Platform::parse_filename_platform(filename) {
+            _ if self.escaping_only => filename,
+            Ok((mut i, platform)) => {
+                push(SegmentKind::PlatformIcon(platform.kind), filename, i);
+
+                let (rest, lowercase_root) = platform.parse_filename_lowercase_root(i)?;
+                if lowercase_root {
+                    push(SegmentKind::LowercaseRoot, i, rest);
+                    i = rest;
+                }
+
+                // the prefix decoded, so a common dir icon follows
+                let mut chars = i.chars();
+                let Some(dir) = chars.next().and_then(CommonDir::from_icon) else {
+                    return Err(Error::ParseError(nom::error::Error { input: i.to_string(), code: nom::error::ErrorKind::Char }));
+                };
+                let rest = chars.as_str();
+                push(SegmentKind::CommonDirIcon(dir), i, rest);
+                i = rest;
+
+                while let Some((rest, dir)) = Platform::parse_registered_dir_icon(i, &self.dirs) {
+                    push(SegmentKind::RegisteredDirIcon(dir.name.clone()), i, rest);
+                    i = rest;
+                }
+
+                let (rest, user) = self.escaper.unescape_path_comp(platform.sep)(i)?;
+                push(SegmentKind::UserToken(user), i, rest);
+                rest
+            },
+            Err(_) => filename,
+        };
+        #[cfg(not(feature = "platform"))]
+        let mut i = filename;
+
+        while !i.is_empty() {
+            if let Ok((rest, _)) = Escaper::parse_trailing_sep(i) {
+                push(SegmentKind::TrailingSeparator, i, rest);
+                i = rest;
+            } else if let Ok((rest, counter)) = Escaper::parse_counter(i) {
+                push(SegmentKind::Counter(counter), i, rest);
+                i = rest;
+            } else if let Ok((rest, (c, n))) = Escaper::parse_compact_escape(i) {
+                push(SegmentKind::CompactEscape(c, n), i, rest);
+                i = rest;
+            } else if let Ok((rest, original)) = Escaper::parse_transliteration(i) {
+                push(SegmentKind::Transliteration(original), i, rest);
+                i = rest;
+            } else {
+                let (rest, _) = self.escaper.unescape_char(i)?;
+                match self.escaper.unescaping_map.get(i.strip_suffix(rest).unwrap_or(i)) {
+                    Some(c) => push(SegmentKind::EscapedChar(*c), i, rest),
+                    None => push(SegmentKind::Literal, i, rest),
+                }
+                i = rest;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A suggested correction of a corrupted encoded filename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// The byte range of the filename to replace.
+    pub span: Range<usize>,
+    /// The text to replace the span with, or `None` when it can't be guessed, like a missing username.
+    pub replacement: Option<String>,
+    pub message: String,
+}
+
+impl Fix {
+    /// Applies the fix to `filename`, if it has a replacement and the span is in `filename`.
+    pub fn apply(&self, filename: &str) -> Option<String> {
+        let replacement = self.replacement.as_ref()?;
+        if self.span.end < self.span.start {
+            return None;
+        }
+        Some(filename.get(..self.span.start)?.to_string() + replacement + filename.get(self.span.end..)?)
+    }
+}
+
+impl Codec {
+    /// Suggests corrections for a filename that doesn't decode or that the encoder would never produce.
+    ///
+    /// Each fix is independent of the others, and its span refers to the given `filename`.
+    pub fn suggest_fix(&self, filename: impl AsRef<str>) -> Vec<Fix> {
+        let filename = filename.as_ref();
+        let parsed = match self.parse(filename) {
+            Ok(parsed) => parsed,
+            Err(Error::ParseError(err)) => {
+                // only the prefix can fail, and escaping the platform icon always makes the filename decodable
+                let pos = filename.len().saturating_sub(err.input.len());
+                let Some(icon) = filename.chars().next() else {
+                    return Vec::new();
+                };
+                // the whole filename is rejected for a header of another codec or an unknown icon
+                if pos == 0 {
+                    return match icon {
+                        HEADER_ICON => vec![Fix { span: 0..icon.len_utf8(), replacement: None, message: "header of another codec, see decode_auto".into() }],
+                        icon => vec![Fix { span: 0..icon.len_utf8(), replacement: Some(String::new()), message: format!("unknown icon {}", icon) }],
+                    };
+                }
+                let prev = filename.get(..pos).and_then(|head| head.chars().next_back()).unwrap_or(icon);
+                let message = match err.input.chars().next() {
+                    Some(c) => format!("unknown icon {} after {}", c, prev),
+                    None => format!("missing directory icon after {}", prev),
+                };
+                return vec![Fix { span: 0..icon.len_utf8(), replacement: Some(self.escaper.escape(&icon.to_string())), message }];
+            },
+            Err(_) => return Vec::new(),
+        };
+
+        let mut fixes = Vec::new();
+        let mut dir_icon = None;
+        for segment in &parsed.segments {
+            match &segment.kind {
+                SegmentKind::CommonDirIcon(dir) => dir_icon = Some(*dir),
+                SegmentKind::UserToken(user) if user.is_empty() => {
+                    let what = if dir_icon == Some(CommonDir::Drive) { "volume" } else { "username" };
+                    // an icon precedes the user
+                    let icon = filename.get(..segment.span.start).and_then(|head| head.chars().next_back()).unwrap_or(HOME_ICON);
+                    fixes.push(Fix { span: segment.span.clone(), replacement: None, message: format!("missing {} after {}", what, icon) });
+                },
+                SegmentKind::Literal => {
+                    for (n, c) in filename.get(segment.span.clone()).unwrap_or_default().char_indices() {
+                        let Some(escaped) = self.escaper.escaping_map.get(&c) else {
+                            continue;
+                        };
+                        let start = segment.span.start + n;
+                        let message = if escaped.chars().all(|e| e == c) {
+                            format!("unpaired {}", c)
+                        } else {
+                            format!("unescaped {}", c)
+                        };
+                        fixes.push(Fix { span: start..start + c.len_utf8(), replacement: Some(escaped.clone()), message });
+                    }
+                },
+                _ => (),
+            }
+        }
+        fixes
+    }
+}
+
+/// Splits an encoded `filename` into segments with their byte spans.
+///
+/// See [`Codec::parse`].
+pub fn parse(filename: impl AsRef<str>) -> Result<Parsed, Error> {
+    Codec::new().parse(filename)
+}
+
+/// Suggests corrections for a filename that doesn't decode or that the encoder would never produce.
+///
+/// See [`Codec::suggest_fix`].
+pub fn suggest_fix(filename: impl AsRef<str>) -> Vec<Fix> {
+    Codec::new().suggest_fix(filename)
+}
+
+#[cfg(all(test, feature = "platform"))]
+mod tests {
+    use super::*;
+
+    fn kinds(parsed: &Parsed, filename: &str) -> Vec<(SegmentKind, String)> {
+        parsed.segments.iter().map(|segment| (segment.kind.clone(), filename[segment.span.clone()].to_string())).collect()
+    }
+
+    #[test]
+    fn segments() {
+        use SegmentKind::*;
+
+        let filename = "🍎📄alice／my＂file＂.txt";
+        assert_eq!(kinds(&parse(filename).unwrap(), filename), vec![
+            (PlatformIcon(PlatformKind::Mac), "🍎".into()),
+            (CommonDirIcon(CommonDir::Documents), "📄".into()),
+            (UserToken("alice".into()), "alice".into()),
+            (EscapedChar('/'), "／".into()),
+            (Literal, "my".into()),
+            (EscapedChar('"'), "＂".into()),
+            (Literal, "file".into()),
+            (EscapedChar('"'), "＂".into()),
+            (Literal, ".txt".into()),
+        ]);
+
+        let filename = "💠🔡🥞🚧C＼：：📂🔢2";
+        let codec = Codec::builder().register_dir('🚧', "Projects").build().unwrap();
+        assert_eq!(kinds(&codec.parse(filename).unwrap(), filename), vec![
+            (PlatformIcon(PlatformKind::Windows), "💠".into()),
+            (LowercaseRoot, "🔡".into()),
+            (CommonDirIcon(CommonDir::Drive), "🥞".into()),
+            (RegisteredDirIcon("Projects".into()), "🚧".into()),
+            (UserToken("C".into()), "C".into()),
+            (EscapedChar('\\'), "＼".into()),
+            (EscapedChar('：'), "：：".into()),
+            (TrailingSeparator, "📂".into()),
+            (Counter(2), "🔢2".into()),
+        ]);
+
+        assert_eq!(parse("").unwrap().segments, vec![]);
+        assert_eq!(kinds(&parse("🏠x").unwrap(), "🏠x"), vec![(Literal, "🏠x".into())]);
+        assert_eq!(parse("🍎invalid"), Err(Error::ParseError(nom::error::Error { input: "invalid".into(), code: nom::error::ErrorKind::Char })));
+
+        let filename = "🧾v1;links=true🧾／a🔀b";
+        let codec = Codec::builder().header(true).link_notation(true).build().unwrap();
+        assert_eq!(kinds(&codec.parse(filename).unwrap(), filename), vec![
+            (Header("v1;links=true".into()), "🧾v1;links=true🧾".into()),
+            (EscapedChar('/'), "／".into()),
+            (Literal, "a".into()),
+            (LinkArrow, "🔀".into()),
+            (Literal, "b".into()),
+        ]);
+    }
+
+    #[test]
+    fn fixes() {
+        let fix = |span: Range<usize>, replacement: Option<&str>, message: &str| Fix { span, replacement: replacement.map(String::from), message: message.into() };
+
+        assert_eq!(suggest_fix("🍎🎨bob／file.png"), vec![]);
+        assert_eq!(suggest_fix("🍎invalid"), vec![fix(0..4, Some("🍏"), "unknown icon i after 🍎")]);
+        assert_eq!(suggest_fix("💠📦alice"), vec![fix(0..4, Some("🚪"), "unknown icon 📦 after 💠")]);
+        assert_eq!(suggest_fix("💠🔡"), vec![fix(0..4, Some("🚪"), "missing directory icon after 🔡")]);
+        assert_eq!(suggest_fix("🍎🏠／file.txt"), vec![fix(8..8, None, "missing username after 🏠")]);
+        assert_eq!(suggest_fix("🐧🥞"), vec![fix(8..8, None, "missing volume after 🥞")]);
+        assert_eq!(suggest_fix("／tmp／🍎:x🔢"), vec![fix(9..13, Some("🍏"), "unescaped 🍎"), fix(13..14, Some("："), "unescaped :"), fix(15..19, Some("🔣"), "unescaped 🔢")]);
+
+        let codec = Codec::builder().register_dir('🚧', "Projects").build().unwrap();
+        assert_eq!(codec.suggest_fix("／tmp／🚧x"), vec![fix(9..13, Some("🚧🚧"), "unpaired 🚧")]);
+        assert_eq!(codec.suggest_fix("🧾v1🧾x"), vec![]);
+        let codec = Codec::builder().header(true).build().unwrap();
+        assert_eq!(codec.suggest_fix("🧾v1;links=true🧾x"), vec![fix(0..4, None, "header of another codec, see decode_auto")]);
+        let codec = Codec::builder().unknown_icons(UnknownIconPolicy::Error).build().unwrap();
+        assert_eq!(codec.suggest_fix("🦀x"), vec![fix(0..4, Some(""), "unknown icon 🦀")]);
+        assert_eq!(fix(0..4, Some(""), "").apply("🦀x"), Some("x".into()));
+
+        assert_eq!(fix(0..4, Some("🍏"), "").apply("🍎invalid"), Some("🍏invalid".into()));
+        assert_eq!(fix(8..8, None, "").apply("🍎🏠／file.txt"), None);
+        for filename in ["🍎invalid", "／tmp／🍎:x🔢", "💠🔡"] {
+            for fix in suggest_fix(filename) {
+                assert!(to_path(fix.apply(filename).unwrap()).is_ok());
+            }
+        }
+    }
+}
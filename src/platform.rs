@@ -0,0 +1,388 @@
+use nom::{
+    bytes::complete::{
+        tag,
+        take_while1,
+    },
+    character::complete::{
+        char,
+        satisfy,
+    },
+    sequence::{
+        preceded,
+        terminated,
+        delimited,
+    },
+    branch::alt,
+    combinator::{
+        eof,
+        map,
+        recognize,
+        success,
+        peek,
+        fail,
+        verify,
+        opt,
+    },
+};
+
+use super::*;
+
+pub(crate) struct Platform {
+    pub(crate) kind: PlatformKind,
+    pub(crate) prefix: char,
+    pub(crate) sep: char,
+    parse_sep: fn(i: &str) -> ParseResult,
+    home_dir: fn(user: &str) -> String,
+    parse_home_dir: fn(i: &str) -> ParseResult,
+    drive_dir: fn(volume: &str) -> String,
+    parse_drive_dir: fn(i: &str) -> ParseResult,
+    music_dir: &'static str,
+    app_data_dir: &'static str,
+    desktop_dir: &'static str,
+    documents_dir: &'static str,
+    downloads_dir: &'static str,
+    pictures_dir: &'static str,
+    videos_dir: &'static str,
+    caches_dir: &'static str,
+}
+
+// holding the usernames and volumes as they are in the path, since escaping them is up to the caller
+enum CommonRootDir<'a> {
+    Home(&'a str),
+    Music(&'a str),
+    AppData(&'a str),
+    Desktop(&'a str),
+    Documents(&'a str),
+    Downloads(&'a str),
+    Pictures(&'a str),
+    Videos(&'a str),
+    Caches(&'a str),
+
+    Drive(&'a str),
+}
+
+impl Platform {
+    fn mac() -> Self {
+        Self {
+            kind: PlatformKind::Mac,
+            prefix: MAC_ICON,
+            sep: POSIX_SEP,
+            parse_sep: Self::parse_posix_sep,
+            home_dir: Self::mac_home_dir,
+            parse_home_dir: Self::parse_mac_home_dir,
+            drive_dir: Self::mac_drive_dir,
+            parse_drive_dir: Self::parse_mac_drive_dir, 
+            app_data_dir: "Library/Application Support",
+            caches_dir: "Library/Caches",
+            ..Platform::default()
+        }
+    }
+
+    fn linux() -> Self {
+        Self {
+            kind: PlatformKind::Linux,
+            prefix: LINUX_ICON,
+            sep: POSIX_SEP,
+            parse_sep: Self::parse_posix_sep,
+            home_dir: Self::linux_home_dir,
+            parse_home_dir: Self::parse_linux_home_dir,
+            drive_dir: Self::linux_drive_dir, 
+            parse_drive_dir: Self::parse_linux_drive_dir, 
+            app_data_dir: ".local/share",
+            caches_dir: ".cache",
+            ..Platform::default()
+        }
+    }
+
+    fn windows() -> Self {
+        Self {
+            kind: PlatformKind::Windows,
+            prefix: WINDOWS_ICON,
+            sep: WINDOWS_SEP,
+            parse_sep: Self::parse_windows_sep,
+            home_dir: Self::windows_home_dir,
+            parse_home_dir: Self::parse_windows_home_dir,
+            drive_dir: Self::windows_drive_dir, 
+            parse_drive_dir: Self::parse_windows_drive_dir, 
+            app_data_dir: "AppData\\Local",
+            ..Platform::default()
+        }
+    }
+
+    fn default() -> Self {
+        Self {
+            kind: PlatformKind::Linux,
+            prefix: LINUX_ICON,
+            sep: POSIX_SEP,
+            parse_sep: Self::parse_fail,
+            home_dir: Self::linux_home_dir,
+            parse_home_dir: Self::parse_fail,
+            drive_dir: Self::linux_drive_dir,
+            parse_drive_dir: Self::parse_fail, 
+            music_dir: "Music",
+            app_data_dir: "AppData",
+            desktop_dir: "Desktop",
+            documents_dir: "Documents",
+            downloads_dir: "Downloads",
+            pictures_dir: "Pictures",
+            videos_dir: "Videos",
+            // empty where there's no per-user cache root, e.g. Windows apps cache under `AppData\Local` itself
+            caches_dir: "",
+        }
+    }
+
+    pub(crate) fn parse_filename_platform(i: &str) -> ParseResult<'_, Self> {
+        alt((
+                map(char(MAC_ICON), |_| Self::mac()),
+                map(char(LINUX_ICON), |_| Self::linux()),
+                map(char(WINDOWS_ICON), |_| Self::windows()),
+        ))(i)
+    }
+
+    // parses the marker telling the root of the original path was spelled in lowercase, like `c:` or `/users`
+    pub(crate) fn parse_filename_lowercase_root<'a>(&self, i: &'a str) -> ParseResult<'a, bool> {
+        map(opt(verify(char(LOWERCASE_ROOT_ICON), |_| self.kind != PlatformKind::Linux)), |c| c.is_some())(i)
+    }
+
+    pub(crate) fn lowercase_root(&self, path: &str) -> String {
+        if self.kind == PlatformKind::Mac {
+            return match path.strip_prefix("/Users/") {
+                Some(rest) => "/users/".to_string() + rest,
+                None => path.to_string(),
+            };
+        }
+        let mut chars = path.chars();
+        match chars.next() {
+            Some(drive) => drive.to_lowercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    // `/users/alice` of the case-insensitive APFS, in the canonical spelling
+    pub(crate) fn uppercase_mac_home(i: &str) -> Option<String> {
+        i.strip_prefix("/users/").map(|rest| "/Users/".to_string() + rest)
+    }
+
+    // uppercases the drive letter of a windows path, and returns whether it was lowercase
+    pub(crate) fn uppercase_root(i: &str) -> Option<String> {
+        let mut chars = i.chars();
+        match (chars.next(), chars.next()) {
+            (Some(drive), Some(':')) if drive.is_ascii_lowercase() => Some(format!("{}:{}", drive.to_ascii_uppercase(), chars.as_str())),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn sniff_path_platform(i: &str) -> ParseResult<'_, Self> {
+        peek(alt((
+                    map(alt((Self::parse_mac_home_dir, Self::parse_mac_drive_dir)), |_| Self::mac()),
+                    map(alt((Self::parse_linux_home_dir, Self::parse_linux_drive_dir)), |_| Self::linux()),
+                    map(alt((Self::parse_windows_home_dir, Self::parse_windows_drive_dir)), |_| Self::windows()),
+        )))(i)
+    }
+
+    pub(crate) fn parse_filename_prefix<'a>(&self, i: &'a str, escaper: &'a Escaper, dirs: &'a [RegisteredDir]) -> ParseResult<'a, String> {
+        let user = || Self::parse_filename_user(self.sep, escaper, dirs);
+        alt((
+                map(preceded(char(HOME_ICON), user()), |(user, sub)| (self.home_dir)(&user) + &sub),
+                map(preceded(char(MUSIC_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.music_dir, sub)),
+                map(preceded(char(APP_DATA_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.app_data_dir, sub)),
+                map(preceded(char(DESKTOP_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.desktop_dir, sub)),
+                map(preceded(char(DOCUMENTS_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.documents_dir, sub)),
+                map(preceded(char(DOWNLOADS_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.downloads_dir, sub)),
+                map(preceded(char(PICTURES_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.pictures_dir, sub)),
+                map(preceded(char(VIDEOS_ICON), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.videos_dir, sub)),
+                map(preceded(verify(char(CACHES_ICON), |_| !self.caches_dir.is_empty()), user()), |(user, sub)| format!("{}{}{}{}", (self.home_dir)(&user), self.sep, self.caches_dir, sub)),
+                map(preceded(char(DRIVE_ICON), user()), |(volume, sub)| (self.drive_dir)(&volume) + &sub),
+        ))(i)
+    }
+
+    // parses the stacked icons of registered dirs and the following user (or volume), and returns the user
+    // and the registered dirs joined as a path suffix
+    fn parse_filename_user<'a>(sep: char, escaper: &'a Escaper, dirs: &'a [RegisteredDir]) -> impl FnMut(&'a str) -> ParseResult<'a, (String, String)> {
+        move |mut i| {
+            let mut sub = String::new();
+            while let Some((rest, dir)) = Self::parse_registered_dir_icon(i, dirs) {
+                sub.push(sep);
+                sub.push_str(&dir.name);
+                i = rest;
+            }
+            let (i, user) = escaper.unescape_path_comp(sep)(i)?;
+            Ok((i, (user, sub)))
+        }
+    }
+
+    pub(crate) fn parse_registered_dir_icon<'a, 'd>(i: &'a str, dirs: &'d [RegisteredDir]) -> Option<(&'a str, &'d RegisteredDir)> {
+        let mut chars = i.chars();
+        let c = chars.next()?;
+        let dir = dirs.iter().find(|dir| dir.icon == c)?;
+        // a literal icon is doubled, and the encoder never stacks the same dir twice in a row, so an odd run
+        // starts with a stacked icon and an even run is made of literals
+        if i.chars().take_while(|&r| r == c).count() % 2 == 0 {
+            return None;
+        }
+        Some((chars.as_str(), dir))
+    }
+
+    // returns the rest and the encoded prefix, where `escape_token` appends the escaped username or volume. it
+    // fails when the common root dir of the path is one of `uncompressed` and it has no sub dir to compress.
+    pub(crate) fn parse_path_prefix<'a>(&self, i: &'a str, dirs: &[RegisteredDir], uncompressed: &[CommonDir], escape_token: impl Fn(&str, &mut String)) -> ParseResult<'a, String> {
+        use CommonRootDir::*;
+
+        let sep = self.parse_sep;
+        // an empty name never matches
+        let name = |dir: CommonDir, name: &'static str| if uncompressed.contains(&dir) { "" } else { name };
+
+        let (mut i, dir) = match (self.parse_home_dir)(i) {
+            Ok((i, user)) => {
+                alt((
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Music, self.music_dir)), peek(alt((sep, eof)))), |_| Music(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::AppData, self.app_data_dir)), peek(alt((sep, eof)))), |_| AppData(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Desktop, self.desktop_dir)), peek(alt((sep, eof)))), |_| Desktop(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Documents, self.documents_dir)), peek(alt((sep, eof)))), |_| Documents(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Downloads, self.downloads_dir)), peek(alt((sep, eof)))), |_| Downloads(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Pictures, self.pictures_dir)), peek(alt((sep, eof)))), |_| Pictures(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Videos, self.videos_dir)), peek(alt((sep, eof)))), |_| Videos(user)),
+                        map(delimited(sep, Self::tag_or_fail(name(CommonDir::Caches, self.caches_dir)), peek(alt((sep, eof)))), |_| Caches(user)),
+                        map(verify(success(()), |_| !uncompressed.contains(&CommonDir::Home)), |_| Home(user)),
+                ))(i)?
+            },
+            Err(_) => {
+                // it's sniffed in advance, so it works where the home dir doesn't
+                let (i, volume) = verify(self.parse_drive_dir, |_: &str| !uncompressed.contains(&CommonDir::Drive))(i)?;
+                (i, Drive(volume))
+            },
+        };
+
+        let mut stack = String::new();
+        let mut last = None;
+        while let Some((rest, dir)) = dirs.iter()
+            .filter(|dir| Some(dir.icon) != last)
+            .find_map(|dir| delimited(sep, tag(dir.name.as_str()), peek(alt((sep, eof))))(i).ok().map(|(rest, _)| (rest, dir))) {
+            stack.push(dir.icon);
+            last = Some(dir.icon);
+            i = rest;
+        }
+
+        let (icon, name) = match dir {
+            Home(user) => (HOME_ICON, user),
+            Music(user) => (MUSIC_ICON, user),
+            AppData(user) => (APP_DATA_ICON, user),
+            Desktop(user) => (DESKTOP_ICON, user),
+            Documents(user) => (DOCUMENTS_ICON, user),
+            Downloads(user) => (DOWNLOADS_ICON, user),
+            Pictures(user) => (PICTURES_ICON, user),
+            Videos(user) => (VIDEOS_ICON, user),
+            Caches(user) => (CACHES_ICON, user),
+            Drive(volume) => (DRIVE_ICON, volume),
+        };
+        let mut prefix = format!("{}{}", icon, stack);
+        escape_token(name, &mut prefix);
+        Ok((i, prefix))
+    }
+
+    fn tag_or_fail<'a>(name: &'a str) -> impl Fn(&'a str) -> ParseResult<'a> {
+        move |i: &'a str| {
+            if name.is_empty() {
+                return fail(i);
+            }
+            tag(name)(i)
+        }
+    }
+
+    fn mac_home_dir(user: &str) -> String {
+        "/Users/".to_string() + user
+    }
+
+    fn linux_home_dir(user: &str) -> String {
+        "/home/".to_string() + user
+    }
+
+    fn windows_home_dir(user: &str) -> String {
+        "C:\\Users\\".to_string() + user
+    }
+
+    fn parse_mac_home_dir(i: &str) -> ParseResult<'_> {
+        delimited(tag("/Users/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
+    }
+
+    fn parse_linux_home_dir(i: &str) -> ParseResult<'_> {
+        delimited(tag("/home/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
+    }
+
+    fn parse_windows_home_dir(i: &str) -> ParseResult<'_> {
+        delimited(tag("C:\\Users\\"), Self::parse_windows_path_comp, peek(alt((Self::parse_windows_sep, eof))))(i)
+    }
+
+    fn mac_drive_dir(volume: &str) -> String {
+        "/Volumes/".to_string() + volume
+    }
+
+    fn linux_drive_dir(volume: &str) -> String {
+        "/media/".to_string() + volume
+    }
+
+    fn windows_drive_dir(volume: &str) -> String {
+        if volume.starts_with('{') {
+            "\\\\?\\Volume".to_string() + volume
+        } else {
+            volume.to_string() + ":"
+        }
+    }
+
+    fn parse_mac_drive_dir(i: &str) -> ParseResult<'_> {
+        delimited(tag("/Volumes/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
+    }
+
+    fn parse_linux_drive_dir(i: &str) -> ParseResult<'_> {
+        delimited(tag("/media/"), Self::parse_posix_path_comp, peek(alt((Self::parse_posix_sep, eof))))(i)
+    }
+
+    fn parse_windows_drive_dir(i: &str) -> ParseResult<'_> {
+        alt((
+                terminated(recognize(satisfy(|c| c.is_alphabetic())), char(':')),
+                Self::parse_windows_volume_guid_dir,
+        ))(i)
+    }
+
+    // `\\?\Volume{GUID}` of a volume mounted without a drive letter, returning `{GUID}`
+    fn parse_windows_volume_guid_dir(i: &str) -> ParseResult<'_> {
+        delimited(
+            tag("\\\\?\\Volume"),
+            recognize(delimited(char('{'), take_while1(|c: char| c.is_ascii_hexdigit() || c == '-'), char('}'))),
+            peek(alt((Self::parse_windows_sep, eof))),
+        )(i)
+    }
+
+    fn parse_posix_sep(i: &str) -> ParseResult<'_> {
+        recognize(char(POSIX_SEP))(i)
+    }
+
+    fn parse_posix_path_comp(i: &str) -> ParseResult<'_> {
+        take_while1(|c| c != POSIX_SEP)(i)
+    }
+
+    fn parse_windows_sep(i: &str) -> ParseResult<'_> {
+        recognize(char(WINDOWS_SEP))(i)
+    }
+
+    fn parse_windows_path_comp(i: &str) -> ParseResult<'_> {
+        take_while1(|c| c != WINDOWS_SEP)(i)
+    }
+
+    fn parse_fail(i: &str) -> ParseResult<'_> {
+        fail(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn just_for_coverage() {
+        assert_eq!(
+            Platform::parse_fail("error"),
+            Err(Err::Error(nom::error::Error { input: "error", code: nom::error::ErrorKind::Fail })),
+        );
+    }
+}
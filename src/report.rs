@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use super::*;
+
+/// What [`Codec::decode_unchecked_report`] found decoding a filename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// The decoded path, or the best guess of it when the filename is [`AnomalyKind::Undecodable`].
+    pub path: PathBuf,
+    /// The counter suffix added by [`Codec::to_unique_filename_in`].
+    pub counter: Option<u32>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl DecodeReport {
+    /// Whether nothing irregular was found, so the filename is the one the codec encodes the path to.
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+/// An irregularity found in a filename, with the byte range it spans.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnomalyKind {
+    /// The filename doesn't decode from the span on, so the path is of decoding it char by char, without the
+    /// platform prefix.
+    Undecodable,
+    /// A char the encoder always escapes, like a raw `:` or `🍎`.
+    UnescapedChar,
+    /// A char the encoder always doubles standing alone, like a `〔` or the icon of a registered dir.
+    UnpairedChar,
+    /// A compact escape of a char that needs no escaping, like `〔41〕` for `A`.
+    RedundantEscape,
+    /// An escape the codec writes another way, like `〔FF0F〕` for `／／`.
+    NonCanonicalEscape,
+    /// A reserved icon in the middle of the filename, like `🏠` after a separator, where it has no meaning.
+    SuspiciousIcon,
+    /// The codec encodes the decoded path to another filename, like after the anomalies above, or for filenames
+    /// of another configuration.
+    NonCanonical,
+}
+
+impl Codec {
+    /// Decodes `filename` without stopping at the first problem, collecting every anomaly in a report, e.g. to
+    /// triage a fuzzing corpus or to build repair tools.
+    ///
+    /// Filenames that don't decode are decoded char by char as far as possible, and the anomalies are reported
+    /// in the order they are found. Filenames over a limit of the codec, like [`CodecBuilder::max_input_len`],
+    /// aren't decoded at all.
+    pub fn decode_unchecked_report(&self, filename: impl AsRef<str>) -> DecodeReport {
+        let filename = filename.as_ref();
+        let mut anomalies = Vec::new();
+        let (path, counter) = match self.to_path_and_counter_from_str(filename) {
+            Ok((path, counter)) => {
+                for segment in self.parse(filename).map(|parsed| parsed.segments).unwrap_or_default() {
+                    self.push_anomalies(filename, &segment, &mut anomalies);
+                }
+                let encoded = path.to_str().map(|path| self.to_filename_from_str(path)).unwrap_or_default();
+                let encoded = match counter {
+                    Some(counter) => format!("{}{}{}", encoded, COUNTER_ICON, counter),
+                    None => encoded,
+                };
+                if encoded != filename {
+                    let message = format!("{} is encoded to {:?}", path.display(), encoded);
+                    anomalies.push(Anomaly { kind: AnomalyKind::NonCanonical, span: 0..filename.len(), message });
+                }
+                (path, counter)
+            },
+            Err(err) => {
+                // the input left where parsing failed, unless the error comes from parsing something else, like a link
+                let start = match &err {
+                    Error::ParseError(err) if filename.ends_with(&err.input) => filename.len() - err.input.len(),
+                    _ => 0,
+                };
+                let limited = matches!(err, Error::LimitExceeded(_));
+                anomalies.push(Anomaly { kind: AnomalyKind::Undecodable, span: start..filename.len(), message: format!("{:?}", err) });
+                if limited {
+                    (PathBuf::new(), None)
+                } else {
+                    self.decode_chars(filename, &mut anomalies)
+                }
+            },
+        };
+        DecodeReport { path, counter, anomalies }
+    }
+
+    // decodes the escapes of `filename` from the start, reading what doesn't decode as literal chars
+    fn decode_chars(&self, filename: &str, anomalies: &mut Vec<Anomaly>) -> (PathBuf, Option<u32>) {
+        let mut path = String::new();
+        let mut counter = None;
+        let mut i = filename;
+        while !i.is_empty() {
+            let start = filename.len() - i.len();
+            let (rest, kind) = if let Ok((rest, _)) = Escaper::parse_trailing_sep(i) {
+                path.push(POSIX_SEP);
+                (rest, SegmentKind::TrailingSeparator)
+            } else if let Ok((rest, n)) = Escaper::parse_counter(i) {
+                counter = Some(n);
+                (rest, SegmentKind::Counter(n))
+            } else if let Ok((rest, (c, n))) = Escaper::parse_compact_escape(i) {
+                path.extend(std::iter::repeat_n(c, n as usize));
+                (rest, SegmentKind::CompactEscape(c, n))
+            } else if let Ok((rest, original)) = Escaper::parse_transliteration(i) {
+                path.push_str(&original);
+                (rest, SegmentKind::Transliteration(original))
+            } else if let Ok((rest, decoded)) = self.escaper.unescape_char(i) {
+                path.push_str(&decoded);
+                match self.escaper.unescaping_map.get(i.strip_suffix(rest).unwrap_or(i)) {
+                    Some(c) => (rest, SegmentKind::EscapedChar(*c)),
+                    None => (rest, SegmentKind::Literal),
+                }
+            } else {
+                let mut chars = i.chars();
+                path.extend(chars.next());
+                (chars.as_str(), SegmentKind::Literal)
+            };
+            let end = filename.len() - rest.len();
+            self.push_anomalies(filename, &Segment { kind, span: start..end }, anomalies);
+            i = rest;
+        }
+        (PathBuf::from(path), counter)
+    }
+
+    fn push_anomalies(&self, filename: &str, segment: &Segment, anomalies: &mut Vec<Anomaly>) {
+        let text = filename.get(segment.span.clone()).unwrap_or_default();
+        let mut push = |kind, span: Range<usize>, message: String| anomalies.push(Anomaly { kind, span, message });
+        match segment.kind {
+            SegmentKind::Literal => {
+                for (n, c) in text.char_indices() {
+                    let span = segment.span.start + n..segment.span.start + n + c.len_utf8();
+                    match self.escaper.escaping_map.get(&c) {
+                        Some(escaped) if escaped.chars().all(|e| e == c) => push(AnomalyKind::UnpairedChar, span, format!("unpaired {}", c)),
+                        Some(escaped) => push(AnomalyKind::UnescapedChar, span, format!("unescaped {}, which is escaped to {}", c, escaped)),
+                        None if RESERVED_ICONS.contains(&c) => push(AnomalyKind::SuspiciousIcon, span, format!("icon {} in the middle", c)),
+                        None => (),
+                    }
+                }
+            },
+            SegmentKind::CompactEscape(c, n) => {
+                let expected = self.escaper.escape(&c.to_string().repeat(n as usize));
+                if !self.escaper.escaping_map.contains_key(&c) {
+                    push(AnomalyKind::RedundantEscape, segment.span.clone(), format!("{} stands for {:?}, which needs no escaping", text, expected));
+                } else if expected != text {
+                    push(AnomalyKind::NonCanonicalEscape, segment.span.clone(), format!("{} is written as {} here", text, expected));
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Decodes `filename`, collecting every anomaly in a report.
+///
+/// See [`Codec::decode_unchecked_report`].
+pub fn decode_unchecked_report(filename: impl AsRef<str>) -> DecodeReport {
+    Codec::new().decode_unchecked_report(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(report: &DecodeReport) -> Vec<(AnomalyKind, Range<usize>)> {
+        report.anomalies.iter().map(|anomaly| (anomaly.kind, anomaly.span.clone())).collect()
+    }
+
+    #[test]
+    fn reports() {
+        use AnomalyKind::*;
+
+        let report = decode_unchecked_report("／tmp／a🔢2");
+        assert!(report.is_clean());
+        assert_eq!((report.path, report.counter), (PathBuf::from("/tmp/a"), Some(2)));
+
+        let report = decode_unchecked_report("／tmp／a〔41〕:b");
+        assert_eq!(report.path, PathBuf::from("/tmp/aA:b"));
+        assert_eq!(kinds(&report), [(RedundantEscape, 10..18), (UnescapedChar, 18..19), (NonCanonical, 0..20)]);
+        assert_eq!(report.anomalies[0].message, "〔41〕 stands for \"A\", which needs no escaping");
+        assert_eq!(report.anomalies[2].message, "/tmp/aA:b is encoded to \"／tmp／aA：b\"");
+
+        let report = decode_unchecked_report("／tmp／〔FF0F〕");
+        assert_eq!(report.path, PathBuf::from("/tmp/／"));
+        assert_eq!(kinds(&report), [(NonCanonicalEscape, 9..19), (NonCanonical, 0..19)]);
+        assert_eq!(report.anomalies[0].message, "〔FF0F〕 is written as ／／ here");
+
+        assert_eq!(kinds(&decode_unchecked_report("／tmp／🏠x")), [(SuspiciousIcon, 9..13)]);
+        let codec = Codec::builder().register_dir('🚧', "Projects").build().unwrap();
+        assert_eq!(kinds(&codec.decode_unchecked_report("／a🚧")), [(UnpairedChar, 4..8), (NonCanonical, 0..8)]);
+
+        let report = Codec::builder().max_input_len(3).build().unwrap().decode_unchecked_report("／tmp");
+        assert_eq!((kinds(&report), report.path), (vec![(Undecodable, 0..6)], PathBuf::new()));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn undecodable_report() {
+        use AnomalyKind::*;
+
+        let report = decode_unchecked_report("🍎invalid:x📂");
+        assert_eq!(report.path, PathBuf::from("🍎invalid:x/"));
+        assert_eq!(kinds(&report), [(Undecodable, 4..17), (UnescapedChar, 0..4), (UnescapedChar, 11..12)]);
+        assert_eq!(report.anomalies[0].message, "ParseError(Error { input: \"invalid:x📂\", code: Char })");
+    }
+}
@@ -0,0 +1,104 @@
+//! Serializes paths as their encoded filenames and deserializes them by decoding, for `#[serde(with)]`.
+//!
+//! ```rust
+//! # #[cfg(feature = "platform")] {
+//! use std::path::PathBuf;
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Entry {
+//!     #[serde(with = "path_to_unicode_filename::serde_encoded")]
+//!     source: PathBuf,
+//!     #[serde(with = "path_to_unicode_filename::serde_encoded::option")]
+//!     link: Option<PathBuf>,
+//! }
+//!
+//! let entry = Entry { source: "/Users/alice/Documents/a.txt".into(), link: None };
+//! assert_eq!(serde_json::to_string(&entry).unwrap(), r#"{"source":"🍎📄alice／a.txt","link":null}"#);
+//! # }
+//! ```
+//!
+//! The default [`Codec`] is used, so the filenames are the ones of [`to_filename`].
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use serde::{
+    de,
+    ser,
+    Deserialize,
+    Deserializer,
+    Serializer,
+};
+
+use super::*;
+
+pub fn serialize<S: Serializer>(path: impl AsRef<Path>, serializer: S) -> Result<S::Ok, S::Error> {
+    let filename = to_filename(path).map_err(|err| ser::Error::custom(format!("couldn't encode the path: {:?}", err)))?;
+    serializer.serialize_str(&filename)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    let filename = String::deserialize(deserializer)?;
+    to_path_from_str(&filename).map_err(|err| de::Error::custom(format!("couldn't decode {:?}: {:?}", filename, err)))
+}
+
+/// The same for `Option<PathBuf>` fields, where `None` is kept as is.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(path: &Option<impl AsRef<Path>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match path {
+            Some(path) => {
+                let filename = to_filename(path).map_err(|err| ser::Error::custom(format!("couldn't encode the path: {:?}", err)))?;
+                serializer.serialize_some(&filename)
+            },
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(filename) => {
+                let path = to_path_from_str(&filename).map_err(|err| de::Error::custom(format!("couldn't decode {:?}: {:?}", filename, err)))?;
+                Ok(Some(path))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entry {
+        #[serde(with = "super")]
+        source: PathBuf,
+        #[serde(with = "option")]
+        link: Option<PathBuf>,
+    }
+
+    #[test]
+    fn roundtrip() {
+        let entry = Entry { source: "/tmp/a b.txt".into(), link: Some("/tmp/c:d".into()) };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"source":"／tmp／a b.txt","link":"／tmp／c：d"}"#);
+        assert_eq!(serde_json::from_str::<Entry>(&json).unwrap(), entry);
+
+        let entry = Entry { source: "/tmp".into(), link: None };
+        assert_eq!(serde_json::from_str::<Entry>(r#"{"source":"／tmp","link":null}"#).unwrap(), entry);
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn undecodable() {
+        let err = serde_json::from_str::<Entry>(r#"{"source":"🍎invalid","link":null}"#).unwrap_err();
+        assert!(err.to_string().starts_with("couldn't decode \"🍎invalid\""), "{}", err);
+    }
+}
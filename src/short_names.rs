@@ -0,0 +1,100 @@
+#[cfg(all(windows, feature = "short-names"))]
+use windows_sys::Win32::Storage::FileSystem::GetLongPathNameW;
+
+/// Whether a path segment looks like a Windows 8.3 short name made for a longer one, like `PROGRA~1` or
+/// `MYDOCU~2.TXT`.
+///
+/// The base before the dot is up to 8 chars ending in `~` and a number, and the extension up to 3 chars, of the
+/// ASCII chars short names are made of. Both cases are accepted, since short names are often typed in
+/// lowercase.
+pub fn is_short_name(segment: impl AsRef<str>) -> bool {
+    let segment = segment.as_ref();
+    let (base, ext) = match segment.rsplit_once('.') {
+        Some((base, ext)) => (base, Some(ext)),
+        None => (segment, None),
+    };
+    if ext.is_some_and(|ext| ext.is_empty() || ext.len() > 3 || !ext.bytes().all(is_short_name_byte)) {
+        return false;
+    }
+    let Some((stem, number)) = base.rsplit_once('~') else {
+        return false;
+    };
+    base.len() <= 8
+        && !stem.is_empty()
+        && stem.bytes().all(is_short_name_byte)
+        && !number.is_empty()
+        && !number.starts_with('0')
+        && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The segments of `path` that look like 8.3 short names, by [`is_short_name`], separated by `/` or `\`.
+///
+/// A filename made from a path with short names doesn't match the one made from the long path of the same file,
+/// so these are the paths to mark or resolve before correlating the artifacts, like with
+/// `CodecBuilder::expand_short_names` on Windows.
+pub fn short_name_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\']).filter(|segment| is_short_name(segment))
+}
+
+fn is_short_name_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'()-@^_`{}~".contains(&b)
+}
+
+// the long form of the longest existing ancestor of `path` followed by the rest, or `None` if none resolves
+#[cfg(all(windows, feature = "short-names"))]
+pub(crate) fn long_path(path: &str) -> Option<String> {
+    let mut ancestor = path;
+    let mut rest = "";
+    loop {
+        if let Some(long) = long_path_name(ancestor) {
+            return Some(long + rest);
+        }
+        let n = ancestor.rfind(['/', '\\'])?;
+        rest = path.get(n..)?;
+        ancestor = ancestor.get(..n)?;
+    }
+}
+
+#[cfg(all(windows, feature = "short-names"))]
+fn long_path_name(path: &str) -> Option<String> {
+    let wide: Vec<u16> = path.encode_utf16().chain([0]).collect();
+    let mut buffer = vec![0u16; 260];
+    loop {
+        // SAFETY: `wide` is nul-terminated and `buffer` is writable for its length
+        let len = unsafe { GetLongPathNameW(wide.as_ptr(), buffer.as_mut_ptr(), buffer.len() as u32) } as usize;
+        match len {
+            0 => return None,
+            // too small, where `len` includes the nul
+            len if len > buffer.len() => buffer.resize(len, 0),
+            len => return String::from_utf16(buffer.get(..len)?).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_names() {
+        for name in ["PROGRA~1", "progra~2", "MYDOC~12.TXT", "A~1", "A~1.B", "$RE~1"] {
+            assert!(is_short_name(name), "{}", name);
+        }
+        for name in ["Program Files", "PROGRA~", "PROGRA~0", "PROGRAM~1X", "LONGNAME~1", "~1", "A~1.", "A~1.TEXT", "A B~1", "文書~1"] {
+            assert!(!is_short_name(name), "{}", name);
+        }
+        assert_eq!(short_name_segments("C:\\PROGRA~1\\Common Files/MICROS~1.TXT").collect::<Vec<_>>(), ["PROGRA~1", "MICROS~1.TXT"]);
+        assert_eq!(short_name_segments("/tmp/a").count(), 0);
+    }
+
+    #[test]
+    #[cfg(all(windows, feature = "short-names"))]
+    fn long_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_str().unwrap();
+        // the temp dir itself may be under a short name, like `C:\Users\RUNNER~1`
+        let long = long_path(dir).unwrap();
+        assert!(short_name_segments(&long).next().is_none());
+        assert_eq!(long_path(&format!("{}\\missing\\x", dir)), Some(long + "\\missing\\x"));
+    }
+}
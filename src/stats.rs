@@ -0,0 +1,118 @@
+use std::sync::atomic::{
+    AtomicU64,
+    Ordering,
+};
+
+use super::*;
+
+/// The counts of the encoder set up with [`CodecBuilder::collect_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    /// Paths encoded into filenames.
+    pub paths: u64,
+    /// Chars of the paths written as escapes, like `／` for `/`, doubled chars or compact escapes.
+    pub chars_escaped: u64,
+    /// Paths, or sides of a link, whose OS and common dir were compressed to icons.
+    pub prefixes_compressed: u64,
+    /// UTF-8 bytes of the paths.
+    pub bytes_in: u64,
+    /// UTF-8 bytes of the filenames, including headers.
+    pub bytes_out: u64,
+}
+
+impl Stats {
+    /// `bytes_out` per `bytes_in`, or `None` before any byte is encoded.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.bytes_in > 0).then(|| self.bytes_out as f64 / self.bytes_in as f64)
+    }
+}
+
+// the counts of `Stats`, shared across threads by the codec clones
+#[derive(Debug, Default)]
+pub(crate) struct StatsCollector {
+    paths: AtomicU64,
+    chars_escaped: AtomicU64,
+    prefixes_compressed: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+impl StatsCollector {
+    pub(crate) fn record_path(&self, path: &str, filename: &str) {
+        self.paths.fetch_add(1, Ordering::Relaxed);
+        self.bytes_in.fetch_add(path.len() as u64, Ordering::Relaxed);
+        self.bytes_out.fetch_add(filename.len() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_name(&self, chars_escaped: usize, prefix_compressed: bool) {
+        self.chars_escaped.fetch_add(chars_escaped as u64, Ordering::Relaxed);
+        self.prefixes_compressed.fetch_add(prefix_compressed as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> Stats {
+        Stats {
+            paths: self.paths.load(Ordering::Relaxed),
+            chars_escaped: self.chars_escaped.load(Ordering::Relaxed),
+            prefixes_compressed: self.prefixes_compressed.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Escaper {
+    // how many original chars the escapes of an encoded name stand for, read like the decoder does
+    pub(crate) fn escaped_chars(&self, mut i: &str) -> usize {
+        let mut count = 0;
+        while !i.is_empty() {
+            if let Ok((rest, (_, n))) = Self::parse_compact_escape(i) {
+                count += n as usize;
+                i = rest;
+            } else if let Ok((rest, original)) = Self::parse_transliteration(i) {
+                count += original.chars().count();
+                i = rest;
+            } else {
+                let mut chars = i.chars();
+                let first = chars.next().map(|c| c.len_utf8()).unwrap_or_default();
+                let second = first + chars.next().map(|c| c.len_utf8()).unwrap_or_default();
+                let (len, escaped) = match (i.get(..second), i.get(..first)) {
+                    (Some(doubled), _) if second > first && self.unescaping_map.contains_key(doubled) => (second, true),
+                    (_, Some(single)) => (first, self.unescaping_map.contains_key(single)),
+                    _ => (i.len(), false),
+                };
+                count += escaped as usize;
+                i = i.get(len..).unwrap_or_default();
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        assert_eq!(Codec::new().stats(), None);
+
+        let codec = Codec::builder().collect_stats(true).compact_escapes(true).build().unwrap();
+        assert_eq!(codec.stats(), Some(Stats::default()));
+        assert_eq!(codec.stats().and_then(|stats| stats.ratio()), None);
+        assert_eq!(codec.to_filename("/tmp/a:b"), Ok("／tmp／a：b".into()));
+        assert_eq!(codec.clone().to_filename("/x／／／／／y"), Ok("／x〔FF0F*5〕y".into()));
+        let stats = codec.stats().unwrap();
+        assert_eq!(stats, Stats { paths: 2, chars_escaped: 9, prefixes_compressed: 0, bytes_in: 26, bytes_out: 31 });
+        assert_eq!(stats.ratio(), Some(31.0 / 26.0));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn prefix_stats() {
+        let codec = Codec::builder().collect_stats(true).link_notation(true).build().unwrap();
+        assert_eq!(codec.to_filename("/home/alice/a b -> /tmp/x"), Ok("🐧🏠alice／a b🔀／tmp／x".into()));
+        assert_eq!(codec.to_filename("C:\\Users\\alice\\Documents\\x?"), Ok("💠📄alice＼x？".into()));
+        let stats = codec.stats().unwrap();
+        assert_eq!((stats.paths, stats.chars_escaped, stats.prefixes_compressed), (2, 5, 2));
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    borrow::Cow,
+    fmt,
+    iter::Peekable,
+    str::Chars,
+};
+
+use nom::multi::separated_list1;
+
+use super::*;
+
+const TRANSLITERATION_SEP: char = '=';
+const CODE_SEP: char = ',';
+
+/// Spells chars outside a repertoire, like Cyrillic ones, in chars of it for [`CodecBuilder::transliterate`].
+///
+/// The encoder writes a run of such chars as a compact escape holding both the spelling and the original chars,
+/// like `〔Privet=41F,440,438,432,435,442〕` for `Привет`, so the filename reads in the repertoire and still
+/// decodes exactly. The spelling is only for reading, and decoding doesn't need the transliterator.
+///
+/// The escapes still hold `〔`, `〕` and `=`, and the other chars the codec escapes are written as their
+/// fullwidth substitutes, like `／` for `/`, so the filenames aren't in the repertoire alone.
+pub trait Transliterator: fmt::Debug + Send + Sync {
+    /// The name of the transliterator in the config string of the codec, like `cyrillic-latin`, which stands
+    /// for its spellings, so it changes whenever they do.
+    fn name(&self) -> &str;
+
+    /// Whether `c` is in the repertoire, so it's kept as is. It's ASCII by default.
+    fn is_allowed(&self, c: char) -> bool {
+        c.is_ascii()
+    }
+
+    /// The spelling of `c`, which is outside the repertoire, or `None` if it has none, so it's only written as
+    /// its code. Chars of the spelling outside the repertoire or escaped by the codec are dropped.
+    fn transliterate(&self, c: char) -> Option<Cow<'_, str>>;
+}
+
+/// Russian, Ukrainian and Belarusian Cyrillic to ASCII Latin, in the ICAO spelling of machine-readable passports,
+/// like `Щука` to `Shchuka`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CyrillicToLatin;
+
+impl Transliterator for CyrillicToLatin {
+    fn name(&self) -> &str {
+        "cyrillic-latin"
+    }
+
+    fn transliterate(&self, c: char) -> Option<Cow<'_, str>> {
+        let lower = c.to_lowercase().next()?;
+        let latin = match lower {
+            'а' => "a", 'б' => "b", 'в' => "v", 'г' => "g", 'ґ' => "g", 'д' => "d", 'е' => "e", 'ё' => "e",
+            'є' => "ie", 'ж' => "zh", 'з' => "z", 'и' => "i", 'і' => "i", 'ї' => "i", 'й' => "i", 'к' => "k",
+            'л' => "l", 'м' => "m", 'н' => "n", 'о' => "o", 'п' => "p", 'р' => "r", 'с' => "s", 'т' => "t",
+            'у' => "u", 'ў' => "u", 'ф' => "f", 'х' => "kh", 'ц' => "ts", 'ч' => "ch", 'ш' => "sh",
+            'щ' => "shch", 'ъ' => "ie", 'ы' => "y", 'ь' => "", 'э' => "e", 'ю' => "iu", 'я' => "ia",
+            _ => return None,
+        };
+        if lower == c {
+            return Some(latin.into());
+        }
+        let mut chars = latin.chars();
+        Some(chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default().into())
+    }
+}
+
+impl Escaper {
+    // writes `c` and the chars following it that are outside the repertoire and kept by the escaper
+    pub(crate) fn push_transliteration(&self, transliterator: &dyn Transliterator, c: char, chars: &mut Peekable<Chars<'_>>, r: &mut String) {
+        let mut run = vec![c];
+        while let Some(c) = chars.next_if(|&c| !self.escaping_map.contains_key(&c) && !transliterator.is_allowed(c)) {
+            run.push(c);
+        }
+        let mut spelling = String::new();
+        for &c in &run {
+            spelling.extend(transliterator.transliterate(c).unwrap_or_default().chars().filter(|&l| {
+                transliterator.is_allowed(l)
+                    && !self.escaping_map.contains_key(&l)
+                    && ![TRANSLITERATION_SEP, CODE_SEP, COMPACT_ESCAPE_CLOSE].contains(&l)
+            }));
+        }
+        if spelling.is_empty() {
+            r.extend(run.into_iter().map(|c| Self::compact_escape(c, 1)));
+            return;
+        }
+        let codes: Vec<_> = run.into_iter().map(|c| format!("{:X}", c as u32)).collect();
+        r.extend([COMPACT_ESCAPE_OPEN.to_string(), spelling, TRANSLITERATION_SEP.to_string(), codes.join(&CODE_SEP.to_string()), COMPACT_ESCAPE_CLOSE.to_string()]);
+    }
+
+    // `〔Privet=41F,440,438,432,435,442〕`, with the original chars
+    pub(crate) fn parse_transliteration(i: &str) -> ParseResult<'_, String> {
+        delimited(
+            pair(char(COMPACT_ESCAPE_OPEN), pair(take_till(|c| [TRANSLITERATION_SEP, COMPACT_ESCAPE_OPEN, COMPACT_ESCAPE_CLOSE].contains(&c)), char(TRANSLITERATION_SEP))),
+            map(separated_list1(char(CODE_SEP), map_opt(hex_digit1, |hex| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32))), String::from_iter),
+            char(COMPACT_ESCAPE_CLOSE),
+        )(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transliteration() {
+        let codec = Codec::builder().transliterate(CyrillicToLatin).build().unwrap();
+        let pairs = [
+            ("/tmp/Привет.txt", "／tmp／〔Privet=41F,440,438,432,435,442〕.txt"),
+            ("/tmp/Щука и ёж", "／tmp／〔Shchuka=429,443,43A,430〕 〔i=438〕 〔ezh=451,436〕"),
+            ("/tmp/Ь", "／tmp／〔42C〕"),
+            ("/tmp/日本/x:y", "／tmp／〔65E5〕〔672C〕／x：y"),
+            ("/tmp/〔a=41〕", "／tmp／〔〔a=41〔3015〕"),
+        ];
+        for (path, filename) in pairs {
+            assert_eq!(codec.to_filename(path), Ok(filename.into()));
+            assert_eq!(codec.to_path(filename), Ok(path.into()));
+            // decoding doesn't need the transliterator
+            assert_eq!(to_path(filename), Ok(path.into()));
+        }
+        assert_eq!(CyrillicToLatin.transliterate('Я'), Some("Ia".into()));
+        assert_eq!(CyrillicToLatin.transliterate('a'), None);
+
+        let segments = parse("／〔Ab=41F,430〕").unwrap().segments;
+        assert_eq!(segments.last(), Some(&Segment { kind: SegmentKind::Transliteration("Па".into()), span: 3..19 }));
+        assert!(to_path("／〔Ab=〕").is_ok_and(|path| path == Path::new("/〔Ab=〕")));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn transliterator_config() {
+        let codec = Codec::builder().transliterate(CyrillicToLatin).header(true).build().unwrap();
+        let config = codec.to_config_string();
+        assert!(config.contains(";translit=cyrillic-latin;"));
+        assert_ne!(config, Codec::new().to_config_string());
+        let restored = Codec::from_config_string(&config).unwrap();
+        assert_eq!(restored.to_filename("/tmp/Я"), Ok("🧾v1;translit=cyrillic-latin🧾／tmp／〔Ia=42F〕".into()));
+        assert_eq!(decode_auto("🧾v1;translit=cyrillic-latin🧾／tmp／〔Ia=42F〕"), Ok("/tmp/Я".into()));
+        assert_eq!(Codec::from_config_string("v1;translit=greek-latin").err(), Some(Error::InvalidConfig("unknown transliterator \"greek-latin\"".into())));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn transliterated_user() {
+        let codec = Codec::builder().transliterate(CyrillicToLatin).build().unwrap();
+        assert_eq!(codec.to_filename("C:\\Users\\Иван\\x"), Ok("💠🏠〔Ivan=418,432,430,43D〕＼x".into()));
+        assert_eq!(to_path("💠🏠〔Ivan=418,432,430,43D〕＼x"), Ok("C:\\Users\\Иван\\x".into()));
+    }
+}
@@ -0,0 +1,163 @@
+use std::ffi::OsStr;
+
+use super::*;
+
+impl Codec {
+    /// Encodes the path of a `file:` URL, like the ones browsers and editors hand out.
+    ///
+    /// The path is percent-decoded, and the query and fragment are ignored. `file:///C:/Users/alice` and the
+    /// legacy `file:///C|/Users/alice` are Windows paths, turned into `C:\Users\alice` before encoding, and a
+    /// host other than `localhost`, like in `file://server/share/x`, makes a UNC path `\\server\share\x`.
+    /// Anything else that isn't a `file:` URL of percent-encoded UTF-8 is [`Error::InvalidUrl`].
+    pub fn to_filename_from_url(&self, url: impl AsRef<str>) -> Result<String, Error> {
+        Ok(self.to_filename_from_str(url_to_path(url.as_ref())?))
+    }
+
+    /// Decodes a filename into a `file:` URL of the original path, the reverse of
+    /// [`Codec::to_filename_from_url`].
+    ///
+    /// Windows paths are written in the `file:///C:/Users/alice` form, UNC paths as `file://server/share`, and
+    /// chars other than the ones allowed in URL paths are percent-encoded as UTF-8. Filenames of relative paths
+    /// are [`Error::InvalidUrl`], since a URL can't point at them.
+    pub fn to_file_url(&self, filename: impl AsRef<OsStr>) -> Result<String, Error> {
+        let path = self.to_path(filename)?;
+        let Some(path) = path.to_str() else {
+            return Err(Error::CouldntEncodeToUtf8(path.into_os_string()));
+        };
+        path_to_url(path)
+    }
+}
+
+/// Encodes the path of a `file:` URL.
+///
+/// See [`Codec::to_filename_from_url`].
+pub fn to_filename_from_url(url: impl AsRef<str>) -> Result<String, Error> {
+    Codec::new().to_filename_from_url(url)
+}
+
+/// Decodes a filename into a `file:` URL of the original path.
+///
+/// See [`Codec::to_file_url`].
+pub fn to_file_url(filename: impl AsRef<OsStr>) -> Result<String, Error> {
+    Codec::new().to_file_url(filename)
+}
+
+fn url_to_path(url: &str) -> Result<String, Error> {
+    let invalid = |message: &str| Error::InvalidUrl(format!("{:?} {}", url, message));
+
+    let rest = match url.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("file:") => url.get(5..).unwrap_or_default(),
+        _ => return Err(invalid("isn't a file URL")),
+    };
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = match rest.strip_prefix("//") {
+        Some(rest) => rest.split_at(rest.find('/').unwrap_or(rest.len())),
+        None => ("", rest),
+    };
+    let decode = |s: &str| percent_decode(s).ok_or_else(|| invalid("isn't percent-encoded UTF-8"));
+    let (host, path) = (decode(host)?, decode(path)?);
+    if !path.is_empty() && !path.starts_with('/') {
+        return Err(invalid("doesn't have an absolute path"));
+    }
+
+    if !host.is_empty() && !host.eq_ignore_ascii_case("localhost") {
+        return Ok(format!("{}{}{}", WINDOWS_SEP, WINDOWS_SEP, host) + &path.replace('/', "\\"));
+    }
+    let bytes = path.as_bytes();
+    match bytes {
+        [b'/', letter, b':' | b'|', ..] if letter.is_ascii_alphabetic() && matches!(bytes.get(3), None | Some(b'/')) => {
+            let rest = path.get(3..).unwrap_or_default();
+            let rest = if rest.is_empty() { "\\".into() } else { rest.replace('/', "\\") };
+            Ok(format!("{}:{}", *letter as char, rest))
+        },
+        [] => Err(invalid("doesn't have a path")),
+        _ => Ok(path),
+    }
+}
+
+fn path_to_url(path: &str) -> Result<String, Error> {
+    let bytes = path.as_bytes();
+    match bytes {
+        [letter, b':', b'\\', ..] if letter.is_ascii_alphabetic() => {
+            let rest = path.get(2..).unwrap_or_default().replace('\\', "/");
+            Ok(format!("file:///{}:{}", *letter as char, percent_encode(&rest)))
+        },
+        [b'\\', b'\\', ..] => {
+            let rest = path.get(2..).unwrap_or_default().replace('\\', "/");
+            Ok(format!("file://{}", percent_encode(&rest)))
+        },
+        [b'/', ..] => Ok(format!("file://{}", percent_encode(path))),
+        _ => Err(Error::InvalidUrl(format!("{:?} isn't an absolute path", path))),
+    }
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::new();
+    let mut rest = s.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = tail.get(2..)?;
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// keeps the unreserved chars, the sub-delims, `:`, `@` and `/`, which RFC 3986 allows in a path
+fn percent_encode(s: &str) -> String {
+    let mut r = String::new();
+    for &byte in s.as_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => r.push(byte as char),
+            b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' | b':' | b'@' | b'/' => r.push(byte as char),
+            _ => r.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_url() {
+        assert_eq!(to_filename_from_url("file:///tmp/my%20file.txt"), Ok("／tmp／my file.txt".into()));
+        assert_eq!(to_filename_from_url("FILE://localhost/tmp/a?q=1#top"), Ok("／tmp／a".into()));
+        assert_eq!(to_filename_from_url("file:/tmp/%E6%97%A5%E6%9C%AC"), Ok("／tmp／日本".into()));
+        assert_eq!(to_filename_from_url("file:///C:/x/y.txt"), to_filename("C:\\x\\y.txt"));
+        assert_eq!(to_filename_from_url("file:///c|/x"), to_filename("c:\\x"));
+        assert_eq!(to_filename_from_url("file:///C:"), to_filename("C:\\"));
+        assert_eq!(to_filename_from_url("file://server/share/a%20b"), to_filename("\\\\server\\share\\a b"));
+
+        assert_eq!(to_filename_from_url("https://example.com/a"), Err(Error::InvalidUrl("\"https://example.com/a\" isn't a file URL".into())));
+        assert_eq!(to_filename_from_url("file:///a%2"), Err(Error::InvalidUrl("\"file:///a%2\" isn't percent-encoded UTF-8".into())));
+        assert_eq!(to_filename_from_url("file:///a%FF"), Err(Error::InvalidUrl("\"file:///a%FF\" isn't percent-encoded UTF-8".into())));
+        assert_eq!(to_filename_from_url("file:a"), Err(Error::InvalidUrl("\"file:a\" doesn't have an absolute path".into())));
+        assert_eq!(to_filename_from_url("file://"), Err(Error::InvalidUrl("\"file://\" doesn't have a path".into())));
+    }
+
+    #[test]
+    #[cfg(feature = "platform")]
+    fn from_url_platform() {
+        assert_eq!(to_filename_from_url("file:///home/alice/Documents/x.txt"), Ok("🐧📄alice／x.txt".into()));
+        assert_eq!(to_filename_from_url("file:///C:/Users/alice/x.txt"), Ok("💠🏠alice＼x.txt".into()));
+    }
+
+    #[test]
+    fn file_url() {
+        assert_eq!(to_file_url("／tmp／my file#1.txt"), Ok("file:///tmp/my%20file%231.txt".into()));
+        assert_eq!(to_file_url("／tmp／日本"), Ok("file:///tmp/%E6%97%A5%E6%9C%AC".into()));
+        assert_eq!(to_file_url("C：＼x＼y z"), Ok("file:///C:/x/y%20z".into()));
+        assert_eq!(path_to_url("\\\\server\\share\\a"), Ok("file://server/share/a".into()));
+        assert_eq!(to_file_url("tmp／a"), Err(Error::InvalidUrl("\"tmp/a\" isn't an absolute path".into())));
+
+        for url in ["file:///tmp/a%20b/%25", "file:///C:/x/y"] {
+            assert_eq!(to_file_url(to_filename_from_url(url).unwrap()).as_deref(), Ok(url));
+        }
+    }
+}